@@ -0,0 +1,4 @@
+#![allow(unused_variables)]
+mod raytracer;
+
+pub use raytracer::*;
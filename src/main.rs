@@ -1,64 +1,221 @@
 #![allow(unused_variables)]
-mod raytracer;
-
 extern crate minifb;
 extern crate png;
 extern crate rand;
+extern crate raytracer;
 extern crate threadpool;
 
 use minifb::{Key, Window, WindowOptions};
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::Path;
-// To use encoder.set()
-use rand::Rng;
-use raytracer::camera::Camera;
+use raytracer::animation::{FrameSequence, Interpolation, Keyframe, Track};
+use raytracer::camera::{camera_basis, Camera, Shutter};
+use raytracer::checkpoint::{load_checkpoint, save_checkpoint};
 use raytracer::color::Color;
-use raytracer::directional_light::DirectionalLight;
+use raytracer::diffuse::{MatteMaterial, OrenNayar};
+use raytracer::film::Film;
+use raytracer::microfacet::Conductor;
+use raytracer::perspective_camera::PerspectiveCamera;
+use raytracer::plane::Plane;
 use raytracer::point_light::PointLight;
+use raytracer::principled::PrincipledMaterial;
+use raytracer::renderer::{RenderSettings, Renderer};
 use raytracer::scene::Scene;
 use raytracer::sphere::Sphere;
-use raytracer::spot_light::SpotLight;
-use raytracer::textured_sphere::TexturedSphere;
+use raytracer::stereo_camera::{StereoCamera, StereoLayout};
+use raytracer::surface::Surface;
+use raytracer::texture::{CheckerTexture, ConstantTexture, TextureMapping};
 use raytracer::vec3::Vec3;
-use std::sync::mpsc::channel;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const WIDTH: usize = 1920;
 const HEIGHT: usize = 1080;
 const BOX_SIDE: usize = 96;
 const MAX_ITERATION: u32 = 5;
-const RAY_PER_PIXEL: u32 = 200;
-const RANDOM_OFFSET_COUNT: usize = RAY_PER_PIXEL as usize * 100;
-
-fn color(r: u8, g: u8, b: u8) -> u32 {
-    (r as u32) << 16 | (g as u32) << 8 | (b as u32)
-}
+const RAY_PER_PIXEL: Option<u32> = Some(200);
+const PROGRESSIVE: bool = true;
+// Set a budget (and no RAY_PER_PIXEL) to get the best image possible in a given time
+const TIME_BUDGET: Option<Duration> = None;
+// Set a layout to render both eyes of a stereo pair in the image, each eye getting half of it
+const STEREO: Option<StereoLayout> = None;
+const CHECKPOINT_PATH: &str = "raytracer.checkpoint";
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+// Set a frame sequence to render an animation, frames already rendered are skipped
+const ANIMATION: Option<FrameSequence> = None;
+/*const ANIMATION: Option<FrameSequence> = Some(FrameSequence {
+    first_frame: 0,
+    last_frame: 96,
+    step: 1,
+    frame_rate: 24.0,
+});*/
 
 fn main() {
+    let resume = std::env::args().any(|arg| arg == "--resume");
+    let checkpoint_path = Path::new(CHECKPOINT_PATH);
+
+    // A resumed render continues with the settings it was started with
+    let (settings, film) = if resume {
+        load_checkpoint(checkpoint_path).unwrap_or_else(|e| {
+            panic!("failed to load checkpoint {}: {}", CHECKPOINT_PATH, e);
+        })
+    } else {
+        let settings = RenderSettings {
+            width: WIDTH,
+            height: HEIGHT,
+            tile_size: BOX_SIDE,
+            max_iteration: MAX_ITERATION,
+            samples_per_pixel: RAY_PER_PIXEL,
+            progressive: PROGRESSIVE,
+            time_budget: TIME_BUDGET,
+        };
+
+        (settings, Film::new(WIDTH, HEIGHT))
+    };
+
     let mut window = Window::new(
         "Raytracer - ESC to exit",
-        WIDTH,
-        HEIGHT,
+        settings.width,
+        settings.height,
         WindowOptions::default(),
     )
     .unwrap_or_else(|e| {
         panic!("{}", e);
     });
 
-    let origin = Vec3::new(0.0, 0.5, 0.0);
-    let lookat = Vec3::new(0.0, 0.5, -1.0);
+    match ANIMATION {
+        Some(sequence) => {
+            for frame in sequence.frames() {
+                let file_name = sequence.get_file_name(frame);
+                if Path::new(&file_name).exists() {
+                    println!("Skipping frame {}, {} already exists", frame, file_name);
+                    continue;
+                }
+
+                println!("Rendering frame {}", frame);
+
+                let time = sequence.get_time(frame);
+                let (camera, scene) = build_frame(&settings, time, sequence.get_frame_duration());
+
+                let mut film = Film::new(settings.width, settings.height);
+                film.set_exposure(camera.get_exposure());
+                if !render(&mut window, settings, camera, scene, &mut film, None) {
+                    return;
+                }
+
+                save_film_as_png(&file_name, &film);
+            }
+        }
+        None => {
+            let (camera, scene) = build_frame(&settings, 0.0, 0.0);
+
+            // A resumed film keeps the exposure it was started with
+            let mut film = film;
+            if !resume {
+                film.set_exposure(camera.get_exposure());
+            }
+
+            if !render(
+                &mut window,
+                settings,
+                camera,
+                scene,
+                &mut film,
+                Some(checkpoint_path),
+            ) {
+                // Keep what has been rendered so far if the window was closed before the end
+                save_checkpoint(checkpoint_path, &settings, &film).unwrap();
+                println!("Rendering interrupted, run with --resume to continue it");
+                return;
+            }
+
+            save_film_as_png("raytracer.png", &film);
+        }
+    }
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        window.update();
+    }
+}
 
-    let camera = Arc::new(Camera::new(
+// Builds the camera and the scene as they are at a given time, the shutter staying open for shutter_duration
+fn build_frame(
+    settings: &RenderSettings,
+    time: f32,
+    shutter_duration: f32,
+) -> (Arc<dyn Camera>, Arc<Scene>) {
+    let camera_position = Track::new(vec![
+        Keyframe::new(0.0, Vec3::new(0.0, 0.5, 0.0), Interpolation::EaseInOut),
+        Keyframe::new(4.0, Vec3::new(-1.0, 1.0, 0.5), Interpolation::EaseInOut),
+    ]);
+    let camera_lookat = Track::constant(Vec3::new(0.0, 0.5, -1.0));
+
+    let light_color = Track::new(vec![
+        Keyframe::new(0.0, (1.0, 1.0, 1.0), Interpolation::Linear),
+        Keyframe::new(4.0, (1.0, 0.8, 0.6), Interpolation::Linear),
+    ]);
+
+    let red_sphere_center = Track::new(vec![
+        Keyframe::new(0.0, Vec3::new(1.5, 0.5, -1.0), Interpolation::CatmullRom),
+        Keyframe::new(2.0, Vec3::new(1.5, 1.5, -1.5), Interpolation::CatmullRom),
+        Keyframe::new(4.0, Vec3::new(1.5, 0.5, -1.0), Interpolation::CatmullRom),
+    ]);
+
+    let origin = camera_position.get_value(time);
+    let lookat = camera_lookat.get_value(time);
+
+    let aspect = settings.width as f32 / settings.height as f32;
+    let eye_aspect = match STEREO {
+        Some(StereoLayout::SideBySide) => aspect / 2.0,
+        Some(StereoLayout::TopBottom) => aspect * 2.0,
+        None => aspect,
+    };
+
+    let center_camera = PerspectiveCamera::new(
         origin,
         lookat,
         Vec3::new(0.0, 1.0, 0.0),
-        WIDTH as f32 / HEIGHT as f32,
+        eye_aspect,
         90.0,
         0.05,
         2.0,
-    ));
+    )
+    .with_shutter(Shutter::new(time, time + shutter_duration));
+
+    let camera: Arc<dyn Camera> = match STEREO {
+        // Eyes 6.5cm apart, converging at the focus distance
+        Some(layout) => {
+            let (right, _, _) = camera_basis(&origin, &lookat, &Vec3::new(0.0, 1.0, 0.0));
+            let eye_offset = 0.0325 * right;
+
+            Arc::new(StereoCamera::new(
+                Box::new(center_camera.with_eye_offset(-eye_offset)),
+                Box::new(center_camera.with_eye_offset(eye_offset)),
+                layout,
+            ))
+        }
+        None => Arc::new(center_camera),
+    };
+
+    /*let camera: Arc<dyn Camera> = Arc::new(OrthographicCamera::new(
+        origin,
+        lookat,
+        Vec3::new(0.0, 1.0, 0.0),
+        settings.width as f32 / settings.height as f32,
+        5.0,
+    ));*/
+
+    // Lights have to be scaled by the saturation radiance of the settings (about 9 here) to keep their brightness
+    /*let camera: Arc<dyn Camera> = Arc::new(PerspectiveCamera::new_physical(
+        origin,
+        lookat,
+        Vec3::new(0.0, 1.0, 0.0),
+        settings.width as f32 / settings.height as f32,
+        &PhysicalCameraSettings::full_frame(35.0, 2.8, 3200.0, 1.0 / 30.0),
+        2.0,
+        time,
+    ));*/
 
     let mut scene = Scene::new();
 
@@ -69,7 +226,7 @@ fn main() {
 
     scene.add_light(Box::new(PointLight::new(
         Vec3::new(0.0, 1.5, -1.0),
-        (1.0, 1.0, 1.0),
+        light_color.get_value(time),
         0.9,
         15.0,
     )));
@@ -88,197 +245,191 @@ fn main() {
         20.0,
     )));*/
 
-    // Ground
-    scene.add_object(Box::new(TexturedSphere::new(
-        Vec3::new(0.0, -10000.0, -1.0),
-        10000.0,
-        Color {
-            r: 1.0,
-            g: 1.0,
-            b: 1.0,
-        },
-        0.2,
-        0.0,
+    // Ground - Matte, so the spheres bleed their color onto it
+    scene.add_object(Box::new(Surface::new(
+        Arc::new(Plane::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Color::black(),
+            0.0,
+            0.0,
+        )),
+        Arc::new(MatteMaterial::new(Arc::new(
+            CheckerTexture::new(
+                Color {
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                },
+                Color::black(),
+                1.0,
+                TextureMapping::Uv,
+            )
+            .with_mirrored_rows(),
+        ))),
     )));
 
-    // Left - Black
-    scene.add_object(Box::new(Sphere::new(
-        Vec3::new(-1.5, 0.5, -1.0),
-        0.5,
-        Color {
-            r: 0.0,
-            g: 0.0,
-            b: 0.0,
-        },
-        0.9,
-        0.0,
+    // Left - Metal, about as blurry as the fuzzy mirror it replaced
+    scene.add_object(Box::new(Surface::new(
+        Arc::new(Sphere::new(
+            Vec3::new(-1.5, 0.5, -1.0),
+            0.5,
+            Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            0.0,
+            0.0,
+        )),
+        Arc::new(Conductor::aluminium(0.1)),
     )));
 
-    // Middle - Yellow
-    scene.add_object(Box::new(Sphere::new(
-        Vec3::new(0.0, 0.75, -2.5),
-        0.75,
-        Color {
-            r: 1.0,
-            g: 1.0,
-            b: 0.0,
-        },
-        0.5,
-        0.0,
+    // Middle - Yellow plastic under a clearcoat, as shiny as the half mirror it replaced
+    scene.add_object(Box::new(Surface::new(
+        Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.75, -2.5),
+            0.75,
+            Color {
+                r: 1.0,
+                g: 1.0,
+                b: 0.0,
+            },
+            0.0,
+            0.0,
+        )),
+        Arc::new(
+            PrincipledMaterial::new(Arc::new(ConstantTexture::new(Color {
+                r: 1.0,
+                g: 1.0,
+                b: 0.0,
+            })))
+            .with_roughness(Arc::new(ConstantTexture::from_value(0.4)))
+            .with_clearcoat(
+                Arc::new(ConstantTexture::from_value(1.0)),
+                Arc::new(ConstantTexture::from_value(0.05)),
+            ),
+        ),
     )));
 
-    // Right - Red
-    scene.add_object(Box::new(Sphere::new(
-        Vec3::new(1.5, 0.5, -1.0),
-        0.5,
-        Color {
-            r: 1.0,
-            g: 0.0,
-            b: 0.0,
-        },
-        0.2,
-        0.0,
+    // Right - Red clay
+    scene.add_object(Box::new(Surface::new(
+        Arc::new(
+            Sphere::new(
+                red_sphere_center.get_value(time),
+                0.5,
+                Color {
+                    r: 1.0,
+                    g: 0.0,
+                    b: 0.0,
+                },
+                0.0,
+                0.0,
+            )
+            .with_motion(red_sphere_center.get_motion(
+                time,
+                time + shutter_duration,
+                4,
+            )),
+        ),
+        Arc::new(OrenNayar::new(
+            Color {
+                r: 1.0,
+                g: 0.0,
+                b: 0.0,
+            },
+            0.3,
+        )),
     )));
 
-    let scene = Arc::new(scene);
-
-    let mut rng = rand::XorShiftRng::new_unseeded();
-    let mut random_offsets: Vec<f32> = vec![0.0; RANDOM_OFFSET_COUNT];
-    if RAY_PER_PIXEL > 1 {
-        for i in 0..RANDOM_OFFSET_COUNT {
-            random_offsets[i] = rng.next_f32() * 2.0 - 1.0;
-        }
-    }
-
-    let random_offsets = Arc::new(random_offsets);
-
-    let box_count_x: usize = WIDTH / BOX_SIDE + if WIDTH % BOX_SIDE != 0 { 1 } else { 0 };
-    let box_count_y: usize = HEIGHT / BOX_SIDE + if HEIGHT % BOX_SIDE != 0 { 1 } else { 0 };
+    (camera, Arc::new(scene))
+}
 
-    let boxes: Vec<usize> = (0..box_count_x * box_count_y).collect();
+// Renders into the film while showing progress in the window, returns false if the window was closed before the end
+fn render(
+    window: &mut Window,
+    settings: RenderSettings,
+    camera: Arc<dyn Camera>,
+    scene: Arc<Scene>,
+    film: &mut Film,
+    checkpoint_path: Option<&Path>,
+) -> bool {
+    let mut renderer = Renderer::new(settings, camera, scene);
 
-    let pool = threadpool::Builder::new()
-        .thread_name(String::from("Raytracer"))
-        .build();
+    let mut screen_buffer: Vec<u32> = vec![0; settings.width * settings.height];
 
-    let (tx, rx) = channel();
+    film.resolve(&mut screen_buffer);
+    window.update_with_buffer(&screen_buffer).unwrap();
 
     let start = Instant::now();
+    let mut last_checkpoint = Instant::now();
 
-    for i in boxes.iter() {
-        let x = i % box_count_x;
-        let y = i / box_count_x;
-
-        let min_x = x * BOX_SIDE;
-        let min_y = y * BOX_SIDE;
-
-        let max_x = (min_x + BOX_SIDE).min(WIDTH);
-        let max_y = (min_y + BOX_SIDE).min(HEIGHT);
-
-        let buffer_width = max_x - min_x;
-        let buffer_height = max_y - min_y;
-
-        let camera = camera.clone();
-        let scene = scene.clone();
-        let random_offsets = random_offsets.clone();
-        let tx = tx.clone();
-
-        pool.execute(move || {
-            let mut buffer = vec![0u32; buffer_width * buffer_height];
-
-            let mut random_offset = 0usize;
-
-            let mut rng = rand::XorShiftRng::new_unseeded();
-
-            for y in 0..buffer_height {
-                let screen_y = (min_y + y) as f32;
-                for x in 0..buffer_width {
-                    let screen_x = (min_x + x) as f32;
-
-                    let mut color_r = 0f32;
-                    let mut color_g = 0f32;
-                    let mut color_b = 0f32;
-                    for i in 0..RAY_PER_PIXEL {
-                        let factor_x =
-                            (screen_x + random_offsets[random_offset + 0]) / WIDTH as f32;
+    renderer.start(film);
 
-                        let factor_y =
-                            (screen_y + random_offsets[random_offset + 1]) / HEIGHT as f32;
-
-                        random_offset += 2;
-                        if random_offset >= random_offsets.len() {
-                            random_offset = 0;
-                        }
-
-                        let ray = camera.get_ray(&mut rng, factor_x, factor_y);
-                        let (_, _, trace_color) = scene.trace(&mut rng, ray, MAX_ITERATION, 0f32);
-
-                        color_r += trace_color.r;
-                        color_g += trace_color.g;
-                        color_b += trace_color.b;
-                    }
-
-                    color_r /= RAY_PER_PIXEL as f32;
-                    color_g /= RAY_PER_PIXEL as f32;
-                    color_b /= RAY_PER_PIXEL as f32;
+    while !renderer.is_finished() {
+        if !window.is_open() || window.is_key_down(Key::Escape) {
+            return false;
+        }
 
-                    let u8_r = (color_r * 255.0).min(255.0) as u8;
-                    let u8_g = (color_g * 255.0).min(255.0) as u8;
-                    let u8_b = (color_b * 255.0).min(255.0) as u8;
+        let progress = renderer.update(film);
 
-                    buffer[(y * buffer_width + x) as usize] = color(u8_r, u8_g, u8_b);
-                }
+        if let Some(checkpoint_path) = checkpoint_path {
+            if !progress.updated_tiles.is_empty()
+                && last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL
+            {
+                save_checkpoint(checkpoint_path, &settings, film).unwrap();
+                last_checkpoint = Instant::now();
             }
+        }
 
-            tx.send((x, y, buffer)).unwrap();
-        });
-    }
-
-    let mut remaining = box_count_x * box_count_y;
-
-    let mut screen_buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
-
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        if remaining > 0 {
-            for (box_x, box_y, box_buffer) in rx.try_iter() {
-                let min_x = box_x * BOX_SIDE;
-                let min_y = box_y * BOX_SIDE;
-
-                let max_x = (min_x + BOX_SIDE).min(WIDTH);
-                let max_y = (min_y + BOX_SIDE).min(HEIGHT);
-
-                let buffer_width = max_x - min_x;
-                let buffer_height = max_y - min_y;
-
-                let mut start_y = min_y;
-                for y in 0..buffer_height {
-                    let mut start_x = min_x;
-                    for x in 0..buffer_width {
-                        screen_buffer[(start_y * WIDTH + start_x)] =
-                            box_buffer[(y * buffer_width + x)];
-                        start_x += 1;
-                    }
-                    start_y += 1;
-                }
-
+        if settings.progressive {
+            if progress.pass_completed {
+                film.resolve(&mut screen_buffer);
                 window.update_with_buffer(&screen_buffer).unwrap();
 
-                remaining -= 1;
-                if remaining == 0 {
-                    let duration = start.elapsed();
+                println!(
+                    "Pass done: {} samples per pixel ({}s)",
+                    renderer.get_samples_done(),
+                    start.elapsed().as_secs_f32()
+                );
+            }
+        } else if !progress.updated_tiles.is_empty() {
+            for tile in progress.updated_tiles.iter() {
+                film.resolve_tile(tile, &mut screen_buffer);
+            }
 
-                    println!("Rendering took {}s", duration.as_secs_f32());
+            window.update_with_buffer(&screen_buffer).unwrap();
+        }
 
-                    save_as_png("raytracer.png", WIDTH as u32, HEIGHT as u32, &screen_buffer);
-                }
-            }
+        if progress.finished {
+            let duration = start.elapsed();
+
+            println!(
+                "Rendering took {}s ({} samples per pixel)",
+                duration.as_secs_f32(),
+                film.get_average_sample_count()
+            );
         }
 
         window.update();
     }
+
+    true
+}
+
+fn save_film_as_png(file_name: &str, film: &Film) {
+    let mut buffer: Vec<u32> = vec![0; film.get_width() * film.get_height()];
+    film.resolve(&mut buffer);
+
+    save_as_png(
+        file_name,
+        film.get_width() as u32,
+        film.get_height() as u32,
+        &buffer,
+    );
 }
 
-fn save_as_png(file_name: &str, width: u32, height: u32, buffer: &Vec<u32>) {
+fn save_as_png(file_name: &str, width: u32, height: u32, buffer: &[u32]) {
     let path = Path::new(file_name);
     let file = File::create(path).unwrap();
     let w = &mut BufWriter::new(file);
@@ -295,7 +446,7 @@ fn save_as_png(file_name: &str, width: u32, height: u32, buffer: &Vec<u32>) {
     for value in buffer.iter() {
         let r = ((value & 0x00FF_0000) >> 16) as u8;
         let g = ((value & 0x0000_FF00) >> 8) as u8;
-        let b = ((value & 0x0000_00FF) >> 0) as u8;
+        let b = (value & 0x0000_00FF) as u8;
 
         png_data.push(r);
         png_data.push(g);
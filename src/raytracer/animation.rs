@@ -0,0 +1,327 @@
+use super::color::Color;
+use super::motion::Motion;
+use super::vec3::Vec3;
+
+// Values which can be interpolated between keyframes
+pub trait Animatable: Copy {
+    fn add(self, other: Self) -> Self;
+
+    fn scale(self, factor: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn add(self, other: f32) -> f32 {
+        self + other
+    }
+
+    fn scale(self, factor: f32) -> f32 {
+        self * factor
+    }
+}
+
+impl Animatable for Vec3 {
+    fn add(self, other: Vec3) -> Vec3 {
+        self + other
+    }
+
+    fn scale(self, factor: f32) -> Vec3 {
+        self * factor
+    }
+}
+
+impl Animatable for Color {
+    fn add(self, other: Color) -> Color {
+        Color {
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+        }
+    }
+
+    fn scale(self, factor: f32) -> Color {
+        Color {
+            r: self.r * factor,
+            g: self.g * factor,
+            b: self.b * factor,
+        }
+    }
+}
+
+// Light colors
+impl Animatable for (f32, f32, f32) {
+    fn add(self, other: (f32, f32, f32)) -> (f32, f32, f32) {
+        (self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+
+    fn scale(self, factor: f32) -> (f32, f32, f32) {
+        (self.0 * factor, self.1 * factor, self.2 * factor)
+    }
+}
+
+// Curve followed from a keyframe to the next one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    // Keeps the value until the next keyframe
+    Step,
+    Linear,
+    // Starts and ends with a null speed
+    EaseInOut,
+    // Smooth curve going through the surrounding keyframes
+    CatmullRom,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe<T: Animatable> {
+    pub time: f32,
+    pub value: T,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Animatable> Keyframe<T> {
+    pub fn new(time: f32, value: T, interpolation: Interpolation) -> Keyframe<T> {
+        Keyframe {
+            time,
+            value,
+            interpolation,
+        }
+    }
+}
+
+// Animated value, constant before its first keyframe and after its last one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T: Animatable> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+fn lerp<T: Animatable>(from: T, to: T, factor: f32) -> T {
+    from.scale(1.0 - factor).add(to.scale(factor))
+}
+
+fn catmull_rom<T: Animatable>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    p0.scale(-0.5 * t3 + t2 - 0.5 * t)
+        .add(p1.scale(1.5 * t3 - 2.5 * t2 + 1.0))
+        .add(p2.scale(-1.5 * t3 + 2.0 * t2 + 0.5 * t))
+        .add(p3.scale(0.5 * t3 - 0.5 * t2))
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Track<T> {
+        assert!(!keyframes.is_empty(), "a track needs at least one keyframe");
+
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Track { keyframes }
+    }
+
+    pub fn constant(value: T) -> Track<T> {
+        Track::new(vec![Keyframe::new(0.0, value, Interpolation::Step)])
+    }
+
+    pub fn get_keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn get_value(&self, time: f32) -> T {
+        let keyframes = &self.keyframes;
+
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return keyframes[0].value;
+        } else if next == keyframes.len() {
+            return keyframes[next - 1].value;
+        }
+
+        let previous = &keyframes[next - 1];
+        let following = &keyframes[next];
+        let factor = (time - previous.time) / (following.time - previous.time);
+
+        match previous.interpolation {
+            Interpolation::Step => previous.value,
+            Interpolation::Linear => lerp(previous.value, following.value, factor),
+            Interpolation::EaseInOut => {
+                let factor = factor * factor * (3.0 - 2.0 * factor);
+                lerp(previous.value, following.value, factor)
+            }
+            Interpolation::CatmullRom => {
+                // Missing neighbours at both ends are replaced by the end keyframes
+                let before = keyframes[next.saturating_sub(2)].value;
+                let after = keyframes[(next + 1).min(keyframes.len() - 1)].value;
+
+                catmull_rom(before, previous.value, following.value, after, factor)
+            }
+        }
+    }
+}
+
+impl Track<Vec3> {
+    // Samples the track during a time interval as a motion relative to its value at the start,
+    // so animated objects get motion blur
+    pub fn get_motion(&self, start_time: f32, end_time: f32, sample_count: u32) -> Motion {
+        if end_time <= start_time || sample_count < 2 {
+            return Motion::Static;
+        }
+
+        let origin = self.get_value(start_time);
+        let keyframes = (0..sample_count)
+            .map(|i| {
+                let time =
+                    start_time + (end_time - start_time) * i as f32 / (sample_count - 1) as f32;
+                (time, self.get_value(time) - origin)
+            })
+            .collect();
+
+        Motion::keyframed(keyframes)
+    }
+}
+
+// Range of frames to render as an image sequence
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameSequence {
+    pub first_frame: u32,
+    pub last_frame: u32,
+    pub step: u32,
+    pub frame_rate: f32,
+}
+
+impl FrameSequence {
+    pub fn frames(&self) -> impl Iterator<Item = u32> {
+        (self.first_frame..=self.last_frame).step_by(self.step.max(1) as usize)
+    }
+
+    pub fn get_time(&self, frame: u32) -> f32 {
+        frame as f32 / self.frame_rate
+    }
+
+    pub fn get_frame_duration(&self) -> f32 {
+        1.0 / self.frame_rate
+    }
+
+    pub fn get_file_name(&self, frame: u32) -> String {
+        format!("frame_{:04}.png", frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(interpolation: Interpolation) -> Track<f32> {
+        Track::new(vec![
+            Keyframe::new(3.0, 4.0, interpolation),
+            Keyframe::new(1.0, 2.0, interpolation),
+            Keyframe::new(5.0, -1.0, interpolation),
+        ])
+    }
+
+    #[test]
+    fn values_are_held_outside_of_the_keyframes() {
+        for interpolation in [
+            Interpolation::Step,
+            Interpolation::Linear,
+            Interpolation::EaseInOut,
+            Interpolation::CatmullRom,
+        ] {
+            let track = track(interpolation);
+
+            assert_eq!(track.get_value(-10.0), 2.0);
+            assert_eq!(track.get_value(1.0), 2.0);
+            assert_eq!(track.get_value(5.0), -1.0);
+            assert_eq!(track.get_value(50.0), -1.0);
+        }
+
+        assert_eq!(Track::constant(7.0).get_value(-1.0), 7.0);
+        assert_eq!(Track::constant(7.0).get_value(1.0), 7.0);
+    }
+
+    #[test]
+    fn interpolations_between_keyframes() {
+        // Keyframes are sorted by time
+        let times: Vec<f32> = track(Interpolation::Step)
+            .get_keyframes()
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .collect();
+        assert_eq!(times, vec![1.0, 3.0, 5.0]);
+
+        assert_eq!(track(Interpolation::Step).get_value(2.0), 2.0);
+        assert_eq!(track(Interpolation::Step).get_value(2.99), 2.0);
+        assert_eq!(track(Interpolation::Step).get_value(3.0), 4.0);
+
+        assert!((track(Interpolation::Linear).get_value(2.0) - 3.0).abs() < 1e-6);
+        assert!((track(Interpolation::Linear).get_value(2.5) - 3.5).abs() < 1e-6);
+        assert!((track(Interpolation::Linear).get_value(4.0) - 1.5).abs() < 1e-6);
+
+        // Same midpoint as linear, but slower at the ends
+        let ease = track(Interpolation::EaseInOut);
+        assert!((ease.get_value(2.0) - 3.0).abs() < 1e-6);
+        assert!(ease.get_value(1.5) < track(Interpolation::Linear).get_value(1.5));
+        assert!(ease.get_value(2.5) > track(Interpolation::Linear).get_value(2.5));
+        assert!((ease.get_value(1.01) - 2.0).abs() < 1e-3);
+
+        // Each keyframe uses its own interpolation up to the next one
+        let mixed = Track::new(vec![
+            Keyframe::new(0.0, 0.0, Interpolation::Step),
+            Keyframe::new(1.0, 1.0, Interpolation::Linear),
+            Keyframe::new(2.0, 3.0, Interpolation::Linear),
+        ]);
+        assert_eq!(mixed.get_value(0.5), 0.0);
+        assert!((mixed.get_value(1.5) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn catmull_rom_goes_through_its_keyframes() {
+        let track = Track::new(vec![
+            Keyframe::new(0.0, Vec3::new(0.0, 0.0, 0.0), Interpolation::CatmullRom),
+            Keyframe::new(1.0, Vec3::new(1.0, 2.0, 0.0), Interpolation::CatmullRom),
+            Keyframe::new(2.0, Vec3::new(3.0, 1.0, -1.0), Interpolation::CatmullRom),
+            Keyframe::new(4.0, Vec3::new(2.0, -2.0, 5.0), Interpolation::CatmullRom),
+        ]);
+
+        for keyframe in track.get_keyframes() {
+            for time in [keyframe.time - 1e-4, keyframe.time, keyframe.time + 1e-4] {
+                let value = track.get_value(time);
+                assert!((value - keyframe.value).length() < 1e-2, "{}", time);
+            }
+        }
+
+        // Points evenly spaced on a line stay on it, at the same pace
+        let line = Track::new(
+            (0..4)
+                .map(|i| Keyframe::new(i as f32, 2.0 * i as f32, Interpolation::CatmullRom))
+                .collect(),
+        );
+        assert!((line.get_value(1.25) - 2.5).abs() < 1e-5);
+        assert!((line.get_value(1.5) - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn frames_follow_the_step() {
+        let sequence = FrameSequence {
+            first_frame: 3,
+            last_frame: 12,
+            step: 4,
+            frame_rate: 24.0,
+        };
+        assert_eq!(sequence.frames().collect::<Vec<_>>(), vec![3, 7, 11]);
+
+        // Both ends are rendered when the step reaches the last frame
+        let sequence = FrameSequence {
+            last_frame: 11,
+            ..sequence
+        };
+        assert_eq!(sequence.frames().collect::<Vec<_>>(), vec![3, 7, 11]);
+
+        // A null step renders every frame rather than looping forever
+        let sequence = FrameSequence {
+            step: 0,
+            last_frame: 6,
+            ..sequence
+        };
+        assert_eq!(sequence.frames().collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+
+        assert!((sequence.get_time(12) - 0.5).abs() < 1e-6);
+        assert_eq!(sequence.get_file_name(7), "frame_0007.png");
+    }
+}
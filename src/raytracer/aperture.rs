@@ -0,0 +1,160 @@
+use super::image::Image;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Distribution of a custom aperture, built from the luminance of an image
+#[derive(Debug)]
+pub struct ApertureMask {
+    cdf: Vec<f32>,
+    width: usize,
+    height: usize,
+}
+
+impl ApertureMask {
+    pub fn new(image: &Image) -> ApertureMask {
+        let mut total = 0.0;
+        let cdf = image
+            .get_pixels()
+            .iter()
+            .map(|color| {
+                total += (0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b).max(0.0);
+                total
+            })
+            .collect();
+
+        ApertureMask {
+            cdf,
+            width: image.get_width(),
+            height: image.get_height(),
+        }
+    }
+
+    fn sample(&self, rng: &mut rand::XorShiftRng) -> (f32, f32) {
+        let total = *self.cdf.last().unwrap_or(&0.0);
+        if total <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let value = rng.next_f32() * total;
+        let index = self
+            .cdf
+            .partition_point(|weight| *weight <= value)
+            .min(self.cdf.len() - 1);
+
+        let x = (index % self.width) as f32 + rng.next_f32();
+        let y = (index / self.width) as f32 + rng.next_f32();
+
+        // Top of the image is up on the lens
+        (
+            x / self.width as f32 * 2.0 - 1.0,
+            1.0 - y / self.height as f32 * 2.0,
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ApertureShape {
+    Disk,
+    // Regular polygon formed by the diaphragm blades, rotation in degrees
+    Polygon { blade_count: u32, rotation: f32 },
+    Mask(Arc<ApertureMask>),
+}
+
+// Shape of the lens opening, which gives its shape to out-of-focus highlights (bokeh)
+#[derive(Clone, Debug)]
+pub struct Aperture {
+    shape: ApertureShape,
+    // Anamorphic lenses squeeze the image horizontally, which stretches the bokeh vertically
+    squeeze: f32,
+}
+
+// Maps a square to a disk while preserving areas (Shirley-Chiu concentric mapping)
+fn sample_disk(rng: &mut rand::XorShiftRng) -> (f32, f32) {
+    let a = rng.next_f32() * 2.0 - 1.0;
+    let b = rng.next_f32() * 2.0 - 1.0;
+
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+
+    (radius * theta.cos(), radius * theta.sin())
+}
+
+fn sample_polygon(rng: &mut rand::XorShiftRng, blade_count: u32, rotation: f32) -> (f32, f32) {
+    let blade_count = blade_count.max(3);
+
+    // Pick one of the triangles going from the center to an edge, then a point inside of it
+    let edge = ((rng.next_f32() * blade_count as f32) as u32).min(blade_count - 1);
+    let angle_step = 2.0 * PI / blade_count as f32;
+    let start_angle = rotation.to_radians() + edge as f32 * angle_step;
+    let end_angle = start_angle + angle_step;
+
+    let mut a = rng.next_f32();
+    let mut b = rng.next_f32();
+    if a + b > 1.0 {
+        a = 1.0 - a;
+        b = 1.0 - b;
+    }
+
+    (
+        a * start_angle.cos() + b * end_angle.cos(),
+        a * start_angle.sin() + b * end_angle.sin(),
+    )
+}
+
+impl Aperture {
+    pub fn new(shape: ApertureShape) -> Aperture {
+        Aperture {
+            shape,
+            squeeze: 1.0,
+        }
+    }
+
+    pub fn disk() -> Aperture {
+        Aperture::new(ApertureShape::Disk)
+    }
+
+    pub fn polygon(blade_count: u32, rotation: f32) -> Aperture {
+        Aperture::new(ApertureShape::Polygon {
+            blade_count,
+            rotation,
+        })
+    }
+
+    pub fn mask(image: &Image) -> Aperture {
+        Aperture::new(ApertureShape::Mask(Arc::new(ApertureMask::new(image))))
+    }
+
+    pub fn with_anamorphic_squeeze(self, squeeze: f32) -> Aperture {
+        Aperture { squeeze, ..self }
+    }
+
+    pub fn get_shape(&self) -> &ApertureShape {
+        &self.shape
+    }
+
+    pub fn get_squeeze(&self) -> f32 {
+        self.squeeze
+    }
+
+    // Returns a point of the aperture, inside of the [-1, 1] square
+    pub fn sample(&self, rng: &mut rand::XorShiftRng) -> (f32, f32) {
+        let (x, y) = match &self.shape {
+            ApertureShape::Disk => sample_disk(rng),
+            ApertureShape::Polygon {
+                blade_count,
+                rotation,
+            } => sample_polygon(rng, *blade_count, *rotation),
+            ApertureShape::Mask(mask) => mask.sample(rng),
+        };
+
+        (x / self.squeeze, y)
+    }
+}
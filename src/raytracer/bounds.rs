@@ -0,0 +1,135 @@
+use super::ray::Ray;
+use super::transform::Transform;
+use super::vec3::Vec3;
+
+// Axis-aligned bounding box
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    pub fn new(min: Vec3, max: Vec3) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    pub fn from_points(points: &[Vec3]) -> BoundingBox {
+        let mut bounds = BoundingBox::new(points[0], points[0]);
+        for point in &points[1..] {
+            bounds = bounds.include(point);
+        }
+
+        bounds
+    }
+
+    // Exact bounds of a disk
+    pub fn from_disk(center: &Vec3, normal: &Vec3, radius: f32) -> BoundingBox {
+        let extent = |component: f32| radius * (1.0 - component * component).max(0.0).sqrt();
+        let extents = Vec3::new(extent(normal.x), extent(normal.y), extent(normal.z));
+
+        BoundingBox::new(center - extents, center + extents)
+    }
+
+    pub fn include(&self, point: &Vec3) -> BoundingBox {
+        BoundingBox {
+            min: Vec3::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            max: Vec3::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        }
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        self.include(&other.min).include(&other.max)
+    }
+
+    // Empty when min is above max along any axis
+    pub fn intersection(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: Vec3::new(
+                self.min.x.max(other.min.x),
+                self.min.y.max(other.min.y),
+                self.min.z.max(other.min.z),
+            ),
+            max: Vec3::new(
+                self.max.x.min(other.max.x),
+                self.max.y.min(other.max.y),
+                self.max.z.min(other.max.z),
+            ),
+        }
+    }
+
+    pub fn get_center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn get_size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn get_corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    // Box enclosing this one once transformed
+    pub fn transform(&self, transform: &Transform) -> BoundingBox {
+        let corners = self
+            .get_corners()
+            .map(|corner| transform.transform_point(&corner));
+
+        BoundingBox::from_points(&corners)
+    }
+
+    // Distances at which the ray enters and leaves the box, if it goes through it
+    pub fn intersect(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let origin = ray.get_origin();
+        let direction = ray.get_direction();
+
+        let mut enter_distance = f32::NEG_INFINITY;
+        let mut exit_distance = f32::INFINITY;
+
+        for (origin, direction, min, max) in [
+            (origin.x, direction.x, self.min.x, self.max.x),
+            (origin.y, direction.y, self.min.y, self.max.y),
+            (origin.z, direction.z, self.min.z, self.max.z),
+        ] {
+            let inv_direction = 1.0 / direction;
+            let mut near = (min - origin) * inv_direction;
+            let mut far = (max - origin) * inv_direction;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+
+            // NaN comes from a ray parallel to a slab, starting on its border
+            if !near.is_nan() {
+                enter_distance = enter_distance.max(near);
+            }
+            if !far.is_nan() {
+                exit_distance = exit_distance.min(far);
+            }
+        }
+
+        if enter_distance <= exit_distance && exit_distance >= 0.0 {
+            Some((enter_distance, exit_distance))
+        } else {
+            None
+        }
+    }
+}
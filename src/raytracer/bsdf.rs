@@ -0,0 +1,31 @@
+use super::color::Color;
+use super::geometry::HitInfo;
+use super::vec3::Vec3;
+
+// Direction picked by a BSDF, with the factor the light coming from it is scaled by
+// (the BSDF times the cosine, divided by the probability of the direction)
+#[derive(Copy, Clone, Debug)]
+pub struct BsdfSample {
+    pub direction: Vec3,
+    pub weight: Color,
+}
+
+// How a surface scatters light, in the tangent frame of the hit: x along the tangent, y along the bitangent
+// and z along the shading normal. Directions point away from the surface, wo toward the viewer.
+pub trait Bsdf {
+    // Ratio of the light coming from wi that leaves toward wo
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color;
+
+    // Incoming direction picked according to the BSDF, None when light is absorbed
+    fn sample(&self, rng: &mut rand::XorShiftRng, wo: &Vec3) -> Option<BsdfSample>;
+
+    // Whether light can go through the surface, which then has a distinct inside
+    fn has_transmission(&self) -> bool {
+        false
+    }
+}
+
+// What a surface is made of, giving the BSDF at each hit so parameters can vary over the surface
+pub trait Material: Sync + Send {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf>;
+}
@@ -2,60 +2,61 @@ use super::ray::Ray;
 use super::vec3::Vec3;
 use rand::Rng;
 
-pub struct Camera {
-    left_corner: Vec3,
-    lens_radius: f32,
-    horizontal: Vec3,
-    position: Vec3,
-    vertical: Vec3,
+pub trait Camera: Sync + Send {
+    // Scale applied to the film
+    fn get_exposure(&self) -> f32 {
+        1.0
+    }
+
+    // x and y are the position on the image, from 0 to 1, starting from the top left corner
+    // No ray is returned for pixels outside of the projection (which are left black)
+    fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Option<Ray>;
+
+    // How much rays spread from one pixel to the next, for a ray cast at x and y with the random numbers of `rng`.
+    // The ray of the next pixel, cast with the same random numbers, gives it.
+    fn get_spread(
+        &self,
+        rng: &rand::XorShiftRng,
+        ray: &Ray,
+        x: f32,
+        y: f32,
+        pixel_width: f32,
+    ) -> f32 {
+        self.get_ray(&mut rng.clone(), x + pixel_width, y)
+            .map_or(0.0, |neighbor| {
+                (neighbor.get_direction() - ray.get_direction()).length()
+            })
+    }
 }
 
-impl Camera {
-    pub fn new(
-        position: Vec3,
-        lookat: Vec3,
-        up: Vec3,
-        aspect: f32,
-        fovy: f32,
-        aperture: f32,
-        focus_dist: f32,
-    ) -> Camera {
-        let lens_radius = aperture / 2.0;
-        let half_theta = fovy.to_radians() / 2.0;
-        let half_height = half_theta.tan();
-        let half_width = half_height * aspect;
-
-        let mut w = position - lookat;
-        w.normalize();
-        let mut u = Vec3::cross_product(&up, &w);
-        u.normalize();
-        let v = Vec3::cross_product(&w, &u);
-
-        let left_corner =
-            position - half_width * focus_dist * u - half_height * focus_dist * v - focus_dist * w;
-        let horizontal = 2.0 * half_width * focus_dist * u;
-        let vertical = 2.0 * half_height * focus_dist * v;
-
-        Camera {
-            left_corner,
-            lens_radius,
-            horizontal,
-            position,
-            vertical,
-        }
+// Interval during which the camera captures light, rays being cast at random moments inside of it
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+}
+
+impl Shutter {
+    pub fn new(open: f32, close: f32) -> Shutter {
+        Shutter { open, close }
     }
 
-    pub fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Ray {
-        let origin = self.position;
-        let offset = Vec3::new(
-            self.lens_radius * (rng.next_f32() * 2.0 - 1.0),
-            self.lens_radius * (rng.next_f32() * 2.0 - 1.0),
-            0.0,
-        );
-
-        Ray::new(
-            origin + offset,
-            self.left_corner + self.horizontal * x + self.vertical * y - origin - offset,
-        )
+    pub fn sample(&self, rng: &mut rand::XorShiftRng) -> f32 {
+        if self.close > self.open {
+            self.open + rng.next_f32() * (self.close - self.open)
+        } else {
+            self.open
+        }
     }
 }
+
+// Returns the (right, up, backward) unit vectors of a camera looking at a point
+pub fn camera_basis(position: &Vec3, lookat: &Vec3, up: &Vec3) -> (Vec3, Vec3, Vec3) {
+    let mut w = position - lookat;
+    w.normalize();
+    let mut u = Vec3::cross_product(up, &w);
+    u.normalize();
+    let v = Vec3::cross_product(&w, &u);
+
+    (u, v, w)
+}
@@ -0,0 +1,311 @@
+use super::color::Color;
+use super::film::Film;
+use super::renderer::RenderSettings;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// Checkpoints store the render settings and the raw film (exposure, accumulated colors and sample counts),
+// floats are written bit for bit so a resumed render matches an uninterrupted one
+const MAGIC: &[u8; 4] = b"RTCP";
+const VERSION: u32 = 3;
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> io::Result<()> {
+    write_u32(writer, value.to_bits())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_bits(read_u32(reader)?))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_settings(writer: &mut impl Write, settings: &RenderSettings) -> io::Result<()> {
+    write_u64(writer, settings.width as u64)?;
+    write_u64(writer, settings.height as u64)?;
+    write_u64(writer, settings.tile_size as u64)?;
+    write_u32(writer, settings.max_iteration)?;
+    write_u32(writer, settings.samples_per_pixel.is_some() as u32)?;
+    write_u32(writer, settings.samples_per_pixel.unwrap_or(0))?;
+    write_u32(writer, settings.progressive as u32)?;
+    match settings.time_budget {
+        Some(budget) => {
+            write_u32(writer, 1)?;
+            write_u64(writer, budget.as_millis() as u64)
+        }
+        None => {
+            write_u32(writer, 0)?;
+            write_u64(writer, 0)
+        }
+    }
+}
+
+fn read_settings(reader: &mut impl Read) -> io::Result<RenderSettings> {
+    let width = read_u64(reader)? as usize;
+    let height = read_u64(reader)? as usize;
+    let tile_size = read_u64(reader)? as usize;
+    let max_iteration = read_u32(reader)?;
+    let has_samples_per_pixel = read_u32(reader)? != 0;
+    let samples_per_pixel = read_u32(reader)?;
+    let progressive = read_u32(reader)? != 0;
+    let has_time_budget = read_u32(reader)? != 0;
+    let time_budget_ms = read_u64(reader)?;
+
+    if width == 0 || height == 0 || tile_size == 0 {
+        return Err(invalid_data("invalid checkpoint image size"));
+    }
+
+    Ok(RenderSettings {
+        width,
+        height,
+        tile_size,
+        max_iteration,
+        samples_per_pixel: if has_samples_per_pixel {
+            Some(samples_per_pixel)
+        } else {
+            None
+        },
+        progressive,
+        time_budget: if has_time_budget {
+            Some(Duration::from_millis(time_budget_ms))
+        } else {
+            None
+        },
+    })
+}
+
+pub fn save_checkpoint(path: &Path, settings: &RenderSettings, film: &Film) -> io::Result<()> {
+    // Write to a temporary file first so a crash while saving doesn't corrupt the previous checkpoint
+    let temp_path = path.with_extension("tmp");
+
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+
+        writer.write_all(MAGIC)?;
+        write_u32(&mut writer, VERSION)?;
+        write_settings(&mut writer, settings)?;
+        write_f32(&mut writer, film.get_exposure())?;
+
+        for color in film.get_accumulation() {
+            write_f32(&mut writer, color.r)?;
+            write_f32(&mut writer, color.g)?;
+            write_f32(&mut writer, color.b)?;
+        }
+
+        for sample_count in film.get_sample_counts() {
+            write_u32(&mut writer, *sample_count)?;
+        }
+
+        writer.flush()?;
+    }
+
+    fs::rename(&temp_path, path)
+}
+
+pub fn load_checkpoint(path: &Path) -> io::Result<(RenderSettings, Film)> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a checkpoint file"));
+    }
+
+    if read_u32(&mut reader)? != VERSION {
+        return Err(invalid_data("unsupported checkpoint version"));
+    }
+
+    let settings = read_settings(&mut reader)?;
+    let exposure = read_f32(&mut reader)?;
+
+    let pixel_count = settings.width * settings.height;
+
+    let mut accumulation = Vec::with_capacity(pixel_count);
+    for _ in 0..pixel_count {
+        accumulation.push(Color {
+            r: read_f32(&mut reader)?,
+            g: read_f32(&mut reader)?,
+            b: read_f32(&mut reader)?,
+        });
+    }
+
+    let mut sample_counts = Vec::with_capacity(pixel_count);
+    for _ in 0..pixel_count {
+        sample_counts.push(read_u32(&mut reader)?);
+    }
+
+    let mut film = Film::from_samples(settings.width, settings.height, accumulation, sample_counts);
+    film.set_exposure(exposure);
+
+    Ok((settings, film))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::camera::Camera;
+    use crate::raytracer::perspective_camera::PerspectiveCamera;
+    use crate::raytracer::point_light::PointLight;
+    use crate::raytracer::renderer::Renderer;
+    use crate::raytracer::scene::Scene;
+    use crate::raytracer::sphere::Sphere;
+    use crate::raytracer::vec3::Vec3;
+    use std::env;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!(
+            "raytracer-{}-{}.checkpoint",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn settings() -> RenderSettings {
+        RenderSettings {
+            width: 24,
+            height: 16,
+            tile_size: 8,
+            max_iteration: 3,
+            samples_per_pixel: Some(8),
+            progressive: true,
+            time_budget: None,
+        }
+    }
+
+    fn camera_and_scene(settings: &RenderSettings) -> (Arc<dyn Camera>, Arc<Scene>) {
+        let camera = PerspectiveCamera::new(
+            Vec3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 0.5, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            settings.width as f32 / settings.height as f32,
+            90.0,
+            0.05,
+            2.0,
+        );
+
+        let mut scene = Scene::new();
+        scene.add_light(Box::new(PointLight::new(
+            Vec3::new(0.0, 1.5, -1.0),
+            (1.0, 1.0, 1.0),
+            0.9,
+            15.0,
+        )));
+        scene.add_object(Box::new(Sphere::new(
+            Vec3::new(0.0, 0.5, -1.5),
+            0.5,
+            Color::new(1.0, 0.5, 0.0),
+            0.5,
+            0.0,
+        )));
+
+        (Arc::new(camera), Arc::new(scene))
+    }
+
+    // Renders until the end, or until a number of passes has been completed
+    fn render(settings: RenderSettings, film: &mut Film, max_passes: Option<usize>) {
+        let (camera, scene) = camera_and_scene(&settings);
+        let mut renderer = Renderer::new(settings, camera, scene);
+        let mut passes = 0;
+
+        renderer.start(film);
+        while !renderer.is_finished() && max_passes != Some(passes) {
+            if renderer.update(film).pass_completed {
+                passes += 1;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let settings = RenderSettings {
+            samples_per_pixel: None,
+            progressive: false,
+            time_budget: Some(Duration::from_millis(1500)),
+            ..settings()
+        };
+
+        let pixel_count = settings.width * settings.height;
+        let accumulation = (0..pixel_count)
+            .map(|i| Color::new(i as f32 * 0.1, -1.5, f32::MAX))
+            .collect();
+        let sample_counts = (0..pixel_count).map(|i| i as u32 * 3).collect();
+        let mut film =
+            Film::from_samples(settings.width, settings.height, accumulation, sample_counts);
+        film.set_exposure(0.0042);
+
+        let path = temp_path("round-trip");
+        save_checkpoint(&path, &settings, &film).unwrap();
+        let (loaded_settings, loaded_film) = load_checkpoint(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(format!("{:?}", loaded_settings), format!("{:?}", settings));
+        assert_eq!(loaded_film.get_exposure(), film.get_exposure());
+        assert_eq!(loaded_film.get_sample_counts(), film.get_sample_counts());
+        assert_eq!(loaded_film.get_accumulation(), film.get_accumulation());
+    }
+
+    #[test]
+    fn checkpoint_rejects_other_files() {
+        let path = temp_path("invalid");
+        fs::write(&path, b"RTCP\x01\x00\x00\x00").unwrap();
+        let error = load_checkpoint(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn resumed_render_matches_uninterrupted_one() {
+        let settings = settings();
+
+        let mut uninterrupted = Film::new(settings.width, settings.height);
+        render(settings, &mut uninterrupted, None);
+
+        let mut interrupted = Film::new(settings.width, settings.height);
+        interrupted.set_exposure(0.5);
+        render(settings, &mut interrupted, Some(2));
+        assert!(interrupted.get_average_sample_count() < 8.0);
+
+        let path = temp_path("resume");
+        save_checkpoint(&path, &settings, &interrupted).unwrap();
+        let (resumed_settings, mut resumed) = load_checkpoint(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        render(resumed_settings, &mut resumed, None);
+
+        assert_eq!(resumed.get_exposure(), 0.5);
+        assert_eq!(
+            resumed.get_sample_counts(),
+            uninterrupted.get_sample_counts()
+        );
+        assert_eq!(resumed.get_accumulation(), uninterrupted.get_accumulation());
+    }
+}
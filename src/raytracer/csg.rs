@@ -0,0 +1,239 @@
+use super::bounds::BoundingBox;
+use super::bsdf::Bsdf;
+use super::color::Color;
+use super::geometry::{Crossing, Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    // Left shape minus the right one
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(&self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => inside_left || inside_right,
+            CsgOperation::Intersection => inside_left && inside_right,
+            CsgOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+// Combination of two closed shapes, which can themselves be CSG nodes.
+// The surface takes the color and factors of the left shape.
+#[derive(Clone)]
+pub struct Csg {
+    left: Arc<dyn Geometry>,
+    operation: CsgOperation,
+    right: Arc<dyn Geometry>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Arc<dyn Geometry>, right: Arc<dyn Geometry>) -> Csg {
+        Csg {
+            left,
+            operation,
+            right,
+        }
+    }
+
+    pub fn union(left: Arc<dyn Geometry>, right: Arc<dyn Geometry>) -> Csg {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Arc<dyn Geometry>, right: Arc<dyn Geometry>) -> Csg {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Arc<dyn Geometry>, right: Arc<dyn Geometry>) -> Csg {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+
+    pub fn get_operation(&self) -> CsgOperation {
+        self.operation
+    }
+}
+
+impl Geometry for Csg {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let intervals = self.get_intervals(ray);
+        let interval = intervals
+            .iter()
+            .find(|interval| interval.exit.distance >= 0.0)?;
+
+        // Like spheres, rays starting inside the shape don't hit it
+        if interval.enter.distance < 0.0 {
+            return None;
+        }
+
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = interval.exit.distance
+        }
+
+        // The hit of the shape the surface belongs to, with its surface coordinates
+        if let Some(hit_info) = hitinfo {
+            *hit_info = interval.enter.hit_info;
+        }
+
+        Some(interval.enter.distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.left.get_color(hit_info)
+    }
+
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Option<Box<dyn Bsdf>> {
+        self.left.get_bsdf(hit_info)
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.left.get_reflection_factor(hit_info)
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.left.get_transparency_factor(hit_info)
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let left = self.left.get_bounds();
+        let right = self.right.get_bounds();
+
+        match self.operation {
+            CsgOperation::Union => Some(left?.union(&right?)),
+            CsgOperation::Intersection => match (left, right) {
+                (Some(left), Some(right)) => Some(left.intersection(&right)),
+                _ => left.or(right),
+            },
+            CsgOperation::Difference => left,
+        }
+    }
+
+    // Sweeps along the ray through the crossings of both shapes, keeping track of which ones
+    // the ray is in
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        let mut crossings = Vec::new();
+        for (intervals, is_left) in [
+            (self.left.get_intervals(ray), true),
+            (self.right.get_intervals(ray), false),
+        ] {
+            for interval in intervals {
+                crossings.push((interval.enter, is_left, true));
+                crossings.push((interval.exit, is_left, false));
+            }
+        }
+
+        crossings.sort_by(|a, b| a.0.distance.total_cmp(&b.0.distance));
+
+        let mut left_depth = 0;
+        let mut right_depth = 0;
+        let mut enter: Option<Crossing> = None;
+        let mut intervals = Vec::new();
+
+        for (mut crossing, is_left, entering) in crossings {
+            let depth = if is_left {
+                &mut left_depth
+            } else {
+                &mut right_depth
+            };
+            *depth += if entering { 1 } else { -1 };
+
+            // The surface of the right shape bounds a difference from the inside
+            if !is_left && self.operation == CsgOperation::Difference {
+                crossing.hit_info.normal = -crossing.hit_info.normal;
+                crossing.hit_info.geometric_normal = -crossing.hit_info.geometric_normal;
+            }
+
+            let inside = self.operation.contains(left_depth > 0, right_depth > 0);
+            match enter {
+                None if inside => enter = Some(crossing),
+                Some(enter_crossing) if !inside => {
+                    intervals.push(Interval {
+                        enter: enter_crossing,
+                        exit: crossing,
+                    });
+                    enter = None;
+                }
+                _ => {}
+            }
+        }
+
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::cuboid::Cuboid;
+    use crate::raytracer::sphere::Sphere;
+    use crate::raytracer::vec3::Vec3;
+
+    fn assert_same_surface(left: &HitInfo, right: &HitInfo) {
+        assert!((left.position - right.position).length() < 1e-5);
+        assert!((left.normal - right.normal).length() < 1e-5);
+        assert!((left.uv.0 - right.uv.0).abs() < 1e-5 && (left.uv.1 - right.uv.1).abs() < 1e-5);
+        assert!((left.dpdu - right.dpdu).length() < 1e-5);
+        assert!((left.dpdv - right.dpdv).length() < 1e-5);
+    }
+
+    // Sphere with a bite taken out of its front
+    fn bitten_sphere() -> (Arc<Sphere>, Arc<Cuboid>, Csg) {
+        let color = Color::new(1.0, 1.0, 1.0);
+        let sphere = Arc::new(Sphere::new(Vec3::zero(), 1.0, color, 0.0, 0.0));
+        let cuboid = Arc::new(Cuboid::new(
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            color,
+            0.0,
+            0.0,
+        ));
+
+        (
+            sphere.clone(),
+            cuboid.clone(),
+            Csg::difference(sphere, cuboid),
+        )
+    }
+
+    #[test]
+    fn hit_keeps_the_surface_coordinates_of_the_left_shape() {
+        let (sphere, _, csg) = bitten_sphere();
+        let ray = Ray::new(Vec3::new(-5.0, 0.3, 0.2), Vec3::new(1.0, 0.0, 0.0));
+
+        let mut expected = HitInfo::new();
+        let mut hit_info = HitInfo::new();
+        let distance = csg.compute_hit(&ray, Some(&mut hit_info), None).unwrap();
+
+        assert_eq!(
+            sphere.compute_hit(&ray, Some(&mut expected), None),
+            Some(distance)
+        );
+        assert_same_surface(&hit_info, &expected);
+    }
+
+    #[test]
+    fn hit_keeps_the_surface_coordinates_of_the_right_shape() {
+        let (_, cuboid, csg) = bitten_sphere();
+        let ray = Ray::new(Vec3::new(0.1, 0.2, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+        let mut hit_info = HitInfo::new();
+        let distance = csg.compute_hit(&ray, Some(&mut hit_info), None).unwrap();
+        assert!((distance - 4.5).abs() < 1e-5);
+
+        // Where the ray leaves the cuboid, facing out of the bite
+        let mut expected = cuboid.get_intervals(&ray)[0].exit.hit_info;
+        expected.normal = -expected.normal;
+
+        assert_same_surface(&hit_info, &expected);
+        assert!(hit_info.normal.z > 0.0 && hit_info.geometric_normal.z > 0.0);
+    }
+}
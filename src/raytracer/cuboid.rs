@@ -0,0 +1,284 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::{Crossing, Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::transform::Transform;
+use super::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cuboid {
+    axes: [Vec3; 3],
+    center: Vec3,
+    color: Color,
+    half_size: [f32; 3],
+    reflection_factor: Option<f32>,
+    transparency_factor: Option<f32>,
+}
+
+impl Cuboid {
+    pub fn new(
+        center: Vec3,
+        size: Vec3,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Cuboid {
+        Cuboid {
+            axes: [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+            center,
+            color,
+            half_size: [size.x / 2.0, size.y / 2.0, size.z / 2.0],
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn from_corners(
+        min: Vec3,
+        max: Vec3,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Cuboid {
+        Cuboid::new(
+            (min + max) * 0.5,
+            max - min,
+            color,
+            reflection_factor,
+            transparency_factor,
+        )
+    }
+
+    // Orients the box around its center, only the rotation part of the transform is kept
+    pub fn with_orientation(self, orientation: &Transform) -> Cuboid {
+        let mut axes = self.axes;
+        for axis in axes.iter_mut() {
+            *axis = orientation.transform_vector(axis);
+            axis.normalize();
+        }
+
+        Cuboid { axes, ..self }
+    }
+
+    pub fn get_center(&self) -> Vec3 {
+        self.center
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    pub fn get_size(&self) -> Vec3 {
+        Vec3::new(
+            self.half_size[0] * 2.0,
+            self.half_size[1] * 2.0,
+            self.half_size[2] * 2.0,
+        )
+    }
+
+    fn local_coordinates(&self, vector: &Vec3) -> [f32; 3] {
+        [
+            Vec3::dot_product(vector, &self.axes[0]),
+            Vec3::dot_product(vector, &self.axes[1]),
+            Vec3::dot_product(vector, &self.axes[2]),
+        ]
+    }
+
+    // Face of a surface position (0 to 5 for -x, +x, -y, +y, -z, +z) with its coordinates on the face,
+    // u and v going from 0 to 1
+    pub fn get_face_uv(&self, position: &Vec3) -> (usize, f32, f32) {
+        let local = self.local_coordinates(&(position - self.center));
+
+        // The face is the one the position is the closest to, relatively to the box size
+        let mut axis = 0;
+        for i in 1..3 {
+            if (local[i] / self.half_size[i]).abs() > (local[axis] / self.half_size[axis]).abs() {
+                axis = i;
+            }
+        }
+
+        let positive = local[axis] > 0.0;
+        let u_axis = (axis + 1) % 3;
+        let v_axis = (axis + 2) % 3;
+
+        let mut u = (local[u_axis] / self.half_size[u_axis] + 1.0) / 2.0;
+        let v = (local[v_axis] / self.half_size[v_axis] + 1.0) / 2.0;
+        if !positive {
+            // Keeps the faces seen from outside with the same handedness
+            u = 1.0 - u;
+        }
+
+        (axis * 2 + positive as usize, u, v)
+    }
+
+    // Derivatives of the position along the uv coordinates of a face
+    pub fn get_face_derivatives(&self, face: usize) -> (Vec3, Vec3) {
+        let axis = face / 2;
+        let u_axis = (axis + 1) % 3;
+        let v_axis = (axis + 2) % 3;
+
+        let mut dpdu = self.axes[u_axis] * (2.0 * self.half_size[u_axis]);
+        if face % 2 == 0 {
+            dpdu = -dpdu;
+        }
+
+        (dpdu, self.axes[v_axis] * (2.0 * self.half_size[v_axis]))
+    }
+
+    fn get_hit_info(&self, position: Vec3, normal: Vec3) -> HitInfo {
+        let (face, u, v) = self.get_face_uv(&position);
+        let (dpdu, dpdv) = self.get_face_derivatives(face);
+
+        let mut hit_info = HitInfo::new();
+        hit_info.position = position;
+        hit_info.normal = normal;
+        hit_info.geometric_normal = normal;
+        hit_info.uv = (u, v);
+        hit_info.dpdu = dpdu;
+        hit_info.dpdv = dpdv;
+
+        hit_info
+    }
+
+    // Slab test, giving the distances and normals where the ray enters and leaves
+    fn intersect(&self, ray: &Ray) -> Option<[(f32, Vec3); 2]> {
+        let origin = self.local_coordinates(&(ray.get_origin() - self.center));
+        let direction = self.local_coordinates(ray.get_direction());
+
+        let mut enter_distance = f32::NEG_INFINITY;
+        let mut exit_distance = f32::INFINITY;
+        let mut enter_axis = 0;
+        let mut exit_axis = 0;
+
+        for axis in 0..3 {
+            let half_size = self.half_size[axis];
+            if direction[axis].abs() < 1e-8 {
+                if origin[axis].abs() > half_size {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let inv_direction = 1.0 / direction[axis];
+            let mut near = (-half_size - origin[axis]) * inv_direction;
+            let mut far = (half_size - origin[axis]) * inv_direction;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+
+            if near > enter_distance {
+                enter_distance = near;
+                enter_axis = axis;
+            }
+
+            if far < exit_distance {
+                exit_distance = far;
+                exit_axis = axis;
+            }
+        }
+
+        if enter_distance > exit_distance {
+            return None;
+        }
+
+        let enter_normal = if direction[enter_axis] > 0.0 {
+            -self.axes[enter_axis]
+        } else {
+            self.axes[enter_axis]
+        };
+
+        let exit_normal = if direction[exit_axis] > 0.0 {
+            self.axes[exit_axis]
+        } else {
+            -self.axes[exit_axis]
+        };
+
+        Some([(enter_distance, enter_normal), (exit_distance, exit_normal)])
+    }
+}
+
+impl Geometry for Cuboid {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let [(enter_distance, enter_normal), (exit_distance, _)] = self.intersect(ray)?;
+        if enter_distance < 0.0 {
+            return None;
+        }
+
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = exit_distance
+        }
+
+        if let Some(hit_info) = hitinfo {
+            *hit_info = self.get_hit_info(ray.point_at(enter_distance), enter_normal);
+        }
+
+        Some(enter_distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.get_color()
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let extents = Vec3::new(
+            (0..3)
+                .map(|i| (self.axes[i].x * self.half_size[i]).abs())
+                .sum(),
+            (0..3)
+                .map(|i| (self.axes[i].y * self.half_size[i]).abs())
+                .sum(),
+            (0..3)
+                .map(|i| (self.axes[i].z * self.half_size[i]).abs())
+                .sum(),
+        );
+
+        Some(BoundingBox::new(
+            self.center - extents,
+            self.center + extents,
+        ))
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        self.intersect(ray)
+            .into_iter()
+            .map(
+                |[(enter_distance, enter_normal), (exit_distance, exit_normal)]| Interval {
+                    enter: Crossing {
+                        distance: enter_distance,
+                        hit_info: self.get_hit_info(ray.point_at(enter_distance), enter_normal),
+                    },
+                    exit: Crossing {
+                        distance: exit_distance,
+                        hit_info: self.get_hit_info(ray.point_at(exit_distance), exit_normal),
+                    },
+                },
+            )
+            .collect()
+    }
+}
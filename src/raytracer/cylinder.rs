@@ -0,0 +1,198 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::{first_hit, pair_intervals, Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::solver::solve_quadratic;
+use super::transform::Frame;
+use super::vec3::Vec3;
+use std::f32::consts::PI;
+
+// Cylinder going from its base center along its axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cylinder {
+    capped: bool,
+    color: Color,
+    frame: Frame,
+    height: f32,
+    radius: f32,
+    reflection_factor: Option<f32>,
+    transparency_factor: Option<f32>,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Vec3,
+        axis: Vec3,
+        radius: f32,
+        height: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Cylinder {
+        Cylinder {
+            capped: true,
+            color,
+            frame: Frame::new(base, axis),
+            height,
+            radius,
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+        }
+    }
+
+    // An uncapped cylinder is an open tube
+    pub fn with_caps(self, capped: bool) -> Cylinder {
+        Cylinder { capped, ..self }
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    pub fn get_height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+
+    // u goes around the axis and v along it, both from 0 to 1.
+    // Positions on the caps get the coordinates of the side border.
+    pub fn get_uv(&self, position: &Vec3) -> (f32, f32) {
+        let local = self.frame.local_point(position);
+        (
+            local.z.atan2(local.x) / (2.0 * PI) + 0.5,
+            (local.y / self.height).clamp(0.0, 1.0),
+        )
+    }
+
+    // Distances where the ray crosses the surface, with the normals there
+    fn get_hits(&self, ray: &Ray) -> Vec<(f32, Vec3)> {
+        let origin = self.frame.local_point(ray.get_origin());
+        let direction = self.frame.local_vector(ray.get_direction());
+
+        let mut hits = Vec::with_capacity(4);
+
+        let a = direction.x * direction.x + direction.z * direction.z;
+        let b = 2.0 * (origin.x * direction.x + origin.z * direction.z);
+        let c = origin.x * origin.x + origin.z * origin.z - self.radius * self.radius;
+        for distance in solve_quadratic(a as f64, b as f64, c as f64) {
+            let distance = distance as f32;
+            let position = origin + direction * distance;
+            if position.y >= 0.0 && position.y <= self.height {
+                hits.push((distance, Vec3::new(position.x, 0.0, position.z)));
+            }
+        }
+
+        if self.capped && direction.y.abs() > 1e-8 {
+            for (cap_y, normal_y) in [(0.0, -1.0), (self.height, 1.0)] {
+                let distance = (cap_y - origin.y) / direction.y;
+                let position = origin + direction * distance;
+                if position.x * position.x + position.z * position.z <= self.radius * self.radius {
+                    hits.push((distance, Vec3::new(0.0, normal_y, 0.0)));
+                }
+            }
+        }
+
+        for (_, normal) in hits.iter_mut() {
+            *normal = self.frame.world_vector(normal);
+        }
+
+        hits
+    }
+
+    fn get_hit_info(&self, position: Vec3, normal: Vec3) -> HitInfo {
+        let mut hit_info = HitInfo::new();
+        hit_info.position = position;
+        hit_info.normal = normal;
+        hit_info.geometric_normal = normal;
+        hit_info.uv = self.get_uv(&position);
+
+        let local = self.frame.local_point(&position);
+        let local_normal = self.frame.local_vector(&normal);
+        let radial = (local.x * local.x + local.z * local.z).sqrt().max(1e-6);
+
+        // On the caps, v goes from the center to the border
+        let on_cap = local_normal.x == 0.0 && local_normal.z == 0.0;
+        let dpdv = if on_cap {
+            Vec3::new(
+                self.radius * local.x / radial,
+                0.0,
+                self.radius * local.z / radial,
+            )
+        } else {
+            Vec3::new(0.0, self.height, 0.0)
+        };
+
+        hit_info.dpdu = self
+            .frame
+            .world_vector(&(2.0 * PI * Vec3::new(-local.z, 0.0, local.x)));
+        hit_info.dpdv = self.frame.world_vector(&dpdv);
+
+        hit_info
+    }
+}
+
+impl Geometry for Cylinder {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let mut hits = self.get_hits(ray);
+
+        let (distance, normal, exit_distance) = first_hit(&mut hits, self.capped)?;
+
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = exit_distance
+        }
+
+        if let Some(hit_info) = hitinfo {
+            *hit_info = self.get_hit_info(ray.point_at(distance), normal);
+        }
+
+        Some(distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.get_color()
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let base = self.frame.get_origin();
+        let axis = self.frame.get_axis();
+
+        let base_bounds = BoundingBox::from_disk(&base, &axis, self.radius);
+        let top_bounds = BoundingBox::from_disk(&(base + axis * self.height), &axis, self.radius);
+
+        Some(base_bounds.union(&top_bounds))
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        if self.capped {
+            pair_intervals(&mut self.get_hits(ray), |distance, normal| {
+                self.get_hit_info(ray.point_at(distance), normal)
+            })
+        } else {
+            Vec::new()
+        }
+    }
+}
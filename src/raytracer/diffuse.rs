@@ -0,0 +1,230 @@
+use super::bsdf::{Bsdf, BsdfSample, Material};
+use super::color::Color;
+use super::geometry::HitInfo;
+use super::texture::Texture;
+use super::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Direction above the surface, with a probability proportional to its cosine with the normal (Malley's method)
+pub fn sample_cosine_hemisphere(u1: f32, u2: f32) -> Vec3 {
+    let radius = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+
+    Vec3::new(radius * phi.cos(), radius * phi.sin(), (1.0 - u1).sqrt())
+}
+
+// Sample of a diffuse BRDF, the cosine canceling out with the probability of the direction
+pub fn sample_diffuse(
+    rng: &mut rand::XorShiftRng,
+    wo: &Vec3,
+    evaluate: impl Fn(&Vec3) -> Color,
+) -> Option<BsdfSample> {
+    if wo.z <= 0.0 {
+        return None;
+    }
+
+    let wi = sample_cosine_hemisphere(rng.next_f32(), rng.next_f32());
+
+    Some(BsdfSample {
+        direction: wi,
+        weight: evaluate(&wi) * PI,
+    })
+}
+
+// Matte surface, scattering light evenly in all directions
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lambertian {
+    albedo: Color,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Lambertian {
+        Lambertian { albedo }
+    }
+
+    pub fn get_albedo(&self) -> Color {
+        self.albedo
+    }
+}
+
+impl Bsdf for Lambertian {
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::black();
+        }
+
+        self.albedo * (1.0 / PI)
+    }
+
+    fn sample(&self, rng: &mut rand::XorShiftRng, wo: &Vec3) -> Option<BsdfSample> {
+        sample_diffuse(rng, wo, |wi| self.evaluate(wo, wi))
+    }
+}
+
+impl Material for Lambertian {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf> {
+        Box::new(*self)
+    }
+}
+
+// Rough matte surface made of tiny Lambertian facets, such as clay or plaster, which look flatter than
+// Lambertian ones as facets facing the light are seen more at grazing angles
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrenNayar {
+    a: f32,
+    albedo: Color,
+    b: f32,
+    sigma: f32,
+}
+
+impl OrenNayar {
+    // `sigma` is the standard deviation of the angle of the facets, in radians, 0 giving a Lambertian surface
+    pub fn new(albedo: Color, sigma: f32) -> OrenNayar {
+        let sigma2 = sigma * sigma;
+
+        OrenNayar {
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            albedo,
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+            sigma,
+        }
+    }
+
+    pub fn get_albedo(&self) -> Color {
+        self.albedo
+    }
+
+    pub fn get_sigma(&self) -> f32 {
+        self.sigma
+    }
+}
+
+impl Bsdf for OrenNayar {
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::black();
+        }
+
+        let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+        let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+
+        // Cosine of the azimuth between both directions, the term vanishing when either one is along the normal
+        let cos_phi = if sin_o > 1e-4 && sin_i > 1e-4 {
+            ((wo.x * wi.x + wo.y * wi.y) / (sin_o * sin_i)).max(0.0)
+        } else {
+            0.0
+        };
+
+        // Sine of the largest angle to the normal, tangent of the smallest one
+        let (sin_alpha, tan_beta) = if wi.z < wo.z {
+            (sin_i, sin_o / wo.z)
+        } else {
+            (sin_o, sin_i / wi.z)
+        };
+
+        self.albedo * ((self.a + self.b * cos_phi * sin_alpha * tan_beta) / PI)
+    }
+
+    fn sample(&self, rng: &mut rand::XorShiftRng, wo: &Vec3) -> Option<BsdfSample> {
+        sample_diffuse(rng, wo, |wi| self.evaluate(wo, wi))
+    }
+}
+
+impl Material for OrenNayar {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf> {
+        Box::new(*self)
+    }
+}
+
+// Matte surface with a textured albedo, Lambertian when its facets are flat and Oren-Nayar otherwise
+#[derive(Clone)]
+pub struct MatteMaterial {
+    albedo: Arc<dyn Texture>,
+    sigma: f32,
+}
+
+impl MatteMaterial {
+    pub fn new(albedo: Arc<dyn Texture>) -> MatteMaterial {
+        MatteMaterial { albedo, sigma: 0.0 }
+    }
+
+    pub fn with_sigma(self, sigma: f32) -> MatteMaterial {
+        MatteMaterial { sigma, ..self }
+    }
+
+    pub fn get_albedo(&self) -> &Arc<dyn Texture> {
+        &self.albedo
+    }
+
+    pub fn get_sigma(&self) -> f32 {
+        self.sigma
+    }
+}
+
+impl Material for MatteMaterial {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf> {
+        let albedo = self.albedo.evaluate(hit_info);
+
+        if self.sigma > 0.0 {
+            Box::new(OrenNayar::new(albedo, self.sigma))
+        } else {
+            Box::new(Lambertian::new(albedo))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn rng() -> rand::XorShiftRng {
+        rand::XorShiftRng::from_seed([1, 2, 3, 4])
+    }
+
+    #[test]
+    fn lambertian_samples_weigh_the_albedo() {
+        let albedo = Color::new(0.8, 0.5, 0.2);
+        let lambertian = Lambertian::new(albedo);
+        let mut rng = rng();
+
+        for &wo in &[Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.6, 0.0, 0.8)] {
+            for _ in 0..1000 {
+                let sample = lambertian.sample(&mut rng, &wo).unwrap();
+
+                assert!(sample.direction.z >= 0.0);
+                assert!((sample.direction.length() - 1.0).abs() < 1e-4);
+                assert!((sample.weight.r - albedo.r).abs() < 1e-5);
+                assert!((sample.weight.g - albedo.g).abs() < 1e-5);
+                assert!((sample.weight.b - albedo.b).abs() < 1e-5);
+            }
+        }
+
+        // Nothing is scattered from below the surface
+        assert!(lambertian
+            .sample(&mut rng, &Vec3::new(0.0, 0.0, -1.0))
+            .is_none());
+    }
+
+    #[test]
+    fn flat_oren_nayar_is_lambertian() {
+        let albedo = Color::new(0.8, 0.5, 0.2);
+        let lambertian = Lambertian::new(albedo);
+        let oren_nayar = OrenNayar::new(albedo, 0.0);
+        let mut rng = rng();
+
+        for _ in 0..1000 {
+            let wo = sample_cosine_hemisphere(rng.next_f32(), rng.next_f32());
+            let wi = sample_cosine_hemisphere(rng.next_f32(), rng.next_f32());
+
+            let expected = lambertian.evaluate(&wo, &wi);
+            let value = oren_nayar.evaluate(&wo, &wi);
+
+            assert!((value.r - expected.r).abs() < 1e-6);
+            assert!((value.g - expected.g).abs() < 1e-6);
+            assert!((value.b - expected.b).abs() < 1e-6);
+        }
+    }
+}
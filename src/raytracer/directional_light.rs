@@ -29,7 +29,7 @@ impl Light for DirectionalLight {
     fn compute_light(&self, scene: &Scene, hit_info: &HitInfo, pixel_color: &mut Color, ray: &Ray) {
         let light_dir = -self.get_direction();
 
-        let diffuse_factor = Vec3::dot_product(&light_dir, &hit_info.normal).max(0.0);
+        let diffuse_factor = hit_info.get_diffuse_factor(&light_dir);
 
         let (light_color_r, light_color_g, light_color_b) = self.get_color();
 
@@ -37,11 +37,39 @@ impl Light for DirectionalLight {
         pixel_color.g *= diffuse_factor * light_color_g;
         pixel_color.b *= diffuse_factor * light_color_b;
 
-        if let Some(_) = scene.intersect(Ray::new(hit_info.position - light_dir * 0.01, light_dir))
+        if scene
+            .intersect(
+                Ray::new(hit_info.position - light_dir * 0.01, light_dir).with_time(ray.get_time()),
+            )
+            .is_some()
         {
             pixel_color.r *= 0.1;
             pixel_color.g *= 0.1;
             pixel_color.b *= 0.1;
         }
     }
+
+    fn get_incident_light(
+        &self,
+        scene: &Scene,
+        position: &Vec3,
+        time: f32,
+    ) -> Option<(Vec3, Color)> {
+        let mut light_dir = -self.get_direction();
+        light_dir.normalize();
+
+        if scene
+            .intersect(Ray::new(position + light_dir * 0.01, light_dir).with_time(time))
+            .is_some()
+        {
+            return None;
+        }
+
+        let (light_color_r, light_color_g, light_color_b) = self.get_color();
+
+        Some((
+            light_dir,
+            Color::new(light_color_r, light_color_g, light_color_b),
+        ))
+    }
 }
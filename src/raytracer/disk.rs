@@ -0,0 +1,162 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::Geometry;
+use super::geometry::HitInfo;
+use super::ray::Ray;
+use super::transform::Frame;
+use super::vec3::Vec3;
+use std::f32::consts::PI;
+
+// Flat disk, with a hole in its middle when it is an annulus
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Disk {
+    color: Color,
+    frame: Frame,
+    inner_radius: f32,
+    outer_radius: f32,
+    reflection_factor: Option<f32>,
+    transparency_factor: Option<f32>,
+}
+
+impl Disk {
+    pub fn new(
+        center: Vec3,
+        normal: Vec3,
+        radius: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Disk {
+        Disk::annulus(
+            center,
+            normal,
+            0.0,
+            radius,
+            color,
+            reflection_factor,
+            transparency_factor,
+        )
+    }
+
+    pub fn annulus(
+        center: Vec3,
+        normal: Vec3,
+        inner_radius: f32,
+        outer_radius: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Disk {
+        Disk {
+            color,
+            frame: Frame::new(center, normal),
+            inner_radius,
+            outer_radius,
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    pub fn get_inner_radius(&self) -> f32 {
+        self.inner_radius
+    }
+
+    pub fn get_outer_radius(&self) -> f32 {
+        self.outer_radius
+    }
+
+    // u goes around the center and v from the inner to the outer radius, both from 0 to 1
+    pub fn get_uv(&self, position: &Vec3) -> (f32, f32) {
+        let local = self.frame.local_point(position);
+        let distance = (local.x * local.x + local.z * local.z).sqrt();
+        (
+            local.z.atan2(local.x) / (2.0 * PI) + 0.5,
+            ((distance - self.inner_radius) / (self.outer_radius - self.inner_radius))
+                .clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl Geometry for Disk {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let origin = self.frame.local_point(ray.get_origin());
+        let direction = self.frame.local_vector(ray.get_direction());
+
+        if direction.y.abs() < 1e-6 {
+            return None;
+        }
+
+        let distance = -origin.y / direction.y;
+        if distance < 0.0 {
+            return None;
+        }
+
+        let position = origin + direction * distance;
+        let squared_distance = position.x * position.x + position.z * position.z;
+        if squared_distance > self.outer_radius * self.outer_radius
+            || squared_distance < self.inner_radius * self.inner_radius
+        {
+            return None;
+        }
+
+        // Disks have no thickness
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = distance
+        }
+
+        if let Some(hit_info) = hitinfo {
+            hit_info.position = ray.point_at(distance);
+            hit_info.normal = self.frame.get_axis();
+            hit_info.geometric_normal = hit_info.normal;
+            hit_info.uv = self.get_uv(&hit_info.position);
+
+            let radial = squared_distance.sqrt().max(1e-6);
+            hit_info.dpdu = self
+                .frame
+                .world_vector(&(2.0 * PI * Vec3::new(-position.z, 0.0, position.x)));
+            hit_info.dpdv = self.frame.world_vector(
+                &((self.outer_radius - self.inner_radius)
+                    * Vec3::new(position.x / radial, 0.0, position.z / radial)),
+            );
+        }
+
+        Some(distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.get_color()
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        Some(BoundingBox::from_disk(
+            &self.frame.get_origin(),
+            &self.frame.get_axis(),
+            self.outer_radius,
+        ))
+    }
+}
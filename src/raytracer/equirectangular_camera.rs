@@ -0,0 +1,81 @@
+use super::camera::{camera_basis, Camera, Shutter};
+use super::ray::Ray;
+use super::vec3::Vec3;
+use std::f32::consts::PI;
+
+// Latitude-longitude panorama covering every direction around the camera,
+// the center of the image being the lookat direction
+pub struct EquirectangularCamera {
+    backward: Vec3,
+    convergence_distance: f32,
+    eye_offset: f32,
+    position: Vec3,
+    right: Vec3,
+    shutter: Shutter,
+    up: Vec3,
+}
+
+impl EquirectangularCamera {
+    pub fn new(position: Vec3, lookat: Vec3, up: Vec3) -> EquirectangularCamera {
+        let (u, v, w) = camera_basis(&position, &lookat, &up);
+
+        EquirectangularCamera {
+            backward: w,
+            convergence_distance: 1.0,
+            eye_offset: 0.0,
+            position,
+            right: u,
+            shutter: Shutter::default(),
+            up: v,
+        }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter) -> EquirectangularCamera {
+        EquirectangularCamera { shutter, ..self }
+    }
+
+    // Omni-directional stereo eye: rays start from a circle of radius |eye_offset| around the position
+    // (negative offsets for the left eye) and converge at the given distance
+    pub fn new_stereo_eye(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        eye_offset: f32,
+        convergence_distance: f32,
+    ) -> EquirectangularCamera {
+        EquirectangularCamera {
+            convergence_distance,
+            eye_offset,
+            ..EquirectangularCamera::new(position, lookat, up)
+        }
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Option<Ray> {
+        let longitude = (x - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - y) * PI;
+
+        let direction = latitude.cos()
+            * (longitude.sin() * self.right - longitude.cos() * self.backward)
+            + latitude.sin() * self.up;
+
+        let time = self.shutter.sample(rng);
+
+        if self.eye_offset == 0.0 {
+            return Some(Ray::new(self.position, direction).with_time(time));
+        }
+
+        // The eye offset fades out towards the poles, where there is no consistent horizontal baseline
+        let tangent = longitude.cos() * self.right + longitude.sin() * self.backward;
+        let offset = self.eye_offset * latitude.cos() * tangent;
+
+        Some(
+            Ray::new(
+                self.position + offset,
+                direction * self.convergence_distance - offset,
+            )
+            .with_time(time),
+        )
+    }
+}
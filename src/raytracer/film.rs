@@ -0,0 +1,131 @@
+use super::color::Color;
+use super::tile::Tile;
+
+// Accumulates samples per pixel, the final color of a pixel being the mean of its samples
+pub struct Film {
+    exposure: f32,
+    width: usize,
+    height: usize,
+    accumulation: Vec<Color>,
+    sample_counts: Vec<u32>,
+}
+
+fn to_pixel(color: &Color, sample_count: u32, exposure: f32) -> u32 {
+    if sample_count == 0 {
+        return 0;
+    }
+
+    let inv_sample_count = exposure / sample_count as f32;
+
+    let r = (color.r * inv_sample_count * 255.0).min(255.0) as u8;
+    let g = (color.g * inv_sample_count * 255.0).min(255.0) as u8;
+    let b = (color.b * inv_sample_count * 255.0).min(255.0) as u8;
+
+    (r as u32) << 16 | (g as u32) << 8 | (b as u32)
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Film {
+        Film {
+            exposure: 1.0,
+            width,
+            height,
+            accumulation: vec![
+                Color {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                };
+                width * height
+            ],
+            sample_counts: vec![0; width * height],
+        }
+    }
+
+    pub fn from_samples(
+        width: usize,
+        height: usize,
+        accumulation: Vec<Color>,
+        sample_counts: Vec<u32>,
+    ) -> Film {
+        assert_eq!(accumulation.len(), width * height);
+        assert_eq!(sample_counts.len(), width * height);
+
+        Film {
+            exposure: 1.0,
+            width,
+            height,
+            accumulation,
+            sample_counts,
+        }
+    }
+
+    pub fn get_accumulation(&self) -> &[Color] {
+        &self.accumulation
+    }
+
+    pub fn get_sample_counts(&self) -> &[u32] {
+        &self.sample_counts
+    }
+
+    pub fn get_exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    // Scale applied to the mean of the samples when resolving the film
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_sample_count(&self, x: usize, y: usize) -> u32 {
+        self.sample_counts[y * self.width + x]
+    }
+
+    pub fn get_average_sample_count(&self) -> f32 {
+        let total: u64 = self.sample_counts.iter().map(|count| *count as u64).sum();
+
+        total as f32 / self.sample_counts.len() as f32
+    }
+
+    // Adds the sum of `sample_count` samples for every pixel of the tile
+    pub fn add_samples(&mut self, tile: &Tile, colors: &[Color], sample_count: u32) {
+        for y in 0..tile.height {
+            let offset = (tile.min_y + y) * self.width + tile.min_x;
+            for x in 0..tile.width {
+                let color = &colors[y * tile.width + x];
+                let accumulated = &mut self.accumulation[offset + x];
+                accumulated.r += color.r;
+                accumulated.g += color.g;
+                accumulated.b += color.b;
+
+                self.sample_counts[offset + x] += sample_count;
+            }
+        }
+    }
+
+    // Writes the whole film to a 0RGB buffer
+    pub fn resolve(&self, buffer: &mut [u32]) {
+        for (i, pixel) in buffer.iter_mut().enumerate() {
+            *pixel = to_pixel(&self.accumulation[i], self.sample_counts[i], self.exposure);
+        }
+    }
+
+    // Writes only the pixels covered by a tile to a 0RGB buffer
+    pub fn resolve_tile(&self, tile: &Tile, buffer: &mut [u32]) {
+        for y in 0..tile.height {
+            let offset = (tile.min_y + y) * self.width + tile.min_x;
+            let row = offset..offset + tile.width;
+            for (i, pixel) in row.clone().zip(buffer[row].iter_mut()) {
+                *pixel = to_pixel(&self.accumulation[i], self.sample_counts[i], self.exposure);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use super::camera::{camera_basis, Camera, Shutter};
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    // Distance from the image center is proportional to the angle from the view direction
+    Equidistant,
+    // Preserves areas, distance from the image center is proportional to sin(angle / 2)
+    Equisolid,
+}
+
+// The image circle fits the height of the image, pixels outside of it are masked
+pub struct FisheyeCamera {
+    aspect: f32,
+    backward: Vec3,
+    half_fov: f32,
+    mapping: FisheyeMapping,
+    position: Vec3,
+    right: Vec3,
+    shutter: Shutter,
+    up: Vec3,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        aspect: f32,
+        fov: f32,
+        mapping: FisheyeMapping,
+    ) -> FisheyeCamera {
+        let (u, v, w) = camera_basis(&position, &lookat, &up);
+
+        FisheyeCamera {
+            aspect,
+            backward: w,
+            half_fov: fov.min(360.0).to_radians() / 2.0,
+            mapping,
+            position,
+            right: u,
+            shutter: Shutter::default(),
+            up: v,
+        }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter) -> FisheyeCamera {
+        FisheyeCamera { shutter, ..self }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Option<Ray> {
+        let image_x = (x - 0.5) * 2.0 * self.aspect;
+        let image_y = (0.5 - y) * 2.0;
+
+        let radius = (image_x * image_x + image_y * image_y).sqrt();
+        if radius > 1.0 {
+            return None;
+        }
+
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => radius * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (radius * (self.half_fov / 2.0).sin()).asin(),
+        };
+
+        let (radial_x, radial_y) = if radius > 0.0 {
+            (image_x / radius, image_y / radius)
+        } else {
+            (0.0, 0.0)
+        };
+
+        let direction = theta.sin() * (radial_x * self.right + radial_y * self.up)
+            - theta.cos() * self.backward;
+
+        Some(Ray::new(self.position, direction).with_time(self.shutter.sample(rng)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn rng() -> rand::XorShiftRng {
+        rand::XorShiftRng::from_seed([1, 2, 3, 4])
+    }
+
+    // Looking from (1, 2, 3) toward +x, with an image 1.5 times as wide as high
+    fn camera(mapping: FisheyeMapping) -> FisheyeCamera {
+        FisheyeCamera::new(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(5.0, 2.0, 3.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.5,
+            150.0,
+            mapping,
+        )
+    }
+
+    // Angle between the ray at a pixel and the view direction, in degrees
+    fn angle(camera: &FisheyeCamera, x: f32, y: f32) -> f32 {
+        let ray = camera.get_ray(&mut rng(), x, y).unwrap();
+        let mut direction = *ray.get_direction();
+        direction.normalize();
+
+        direction.x.clamp(-1.0, 1.0).acos().to_degrees()
+    }
+
+    #[test]
+    fn pixels_outside_of_the_circle_are_masked() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let camera = camera(mapping);
+
+            for (x, y) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.05, 0.5)] {
+                assert!(camera.get_ray(&mut rng(), x, y).is_none(), "{} {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn center_looks_at_the_target() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let ray = camera(mapping).get_ray(&mut rng(), 0.5, 0.5).unwrap();
+
+            assert!((*ray.get_origin() - Vec3::new(1.0, 2.0, 3.0)).length() < 1e-6);
+            assert!((*ray.get_direction() - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn circle_edge_is_at_half_the_field_of_view() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let camera = camera(mapping);
+
+            // Top, bottom, and left and right, the circle fitting the height. Points are taken a hair
+            // inside, as rounding could put them out of the circle.
+            let radius = 0.5 * 0.99999;
+            let edges = [
+                (0.5, 0.5 - radius),
+                (0.5, 0.5 + radius),
+                (0.5 - radius / 1.5, 0.5),
+                (0.5 + radius / 1.5, 0.5),
+            ];
+            for (x, y) in edges {
+                let angle = angle(&camera, x, y);
+                assert!((angle - 75.0).abs() < 1e-2, "{:?} {}", mapping, angle);
+            }
+
+            // Up is up, and right is right, which is toward +z when looking toward +x
+            let ray = camera.get_ray(&mut rng(), 0.5, 0.5 - radius).unwrap();
+            assert!(ray.get_direction().y > 0.9);
+            let ray = camera.get_ray(&mut rng(), 0.5 + radius / 1.5, 0.5).unwrap();
+            assert!(ray.get_direction().z > 0.9);
+        }
+
+        // Halfway to the edge, equidistant maps half the angle, equisolid a bit less
+        let equidistant = angle(&camera(FisheyeMapping::Equidistant), 0.5, 0.25);
+        let equisolid = angle(&camera(FisheyeMapping::Equisolid), 0.5, 0.25);
+        assert!((equidistant - 37.5).abs() < 1e-2);
+        let expected = 2.0 * (0.5 * 37.5f32.to_radians().sin()).asin().to_degrees();
+        assert!((equisolid - expected).abs() < 1e-2);
+        assert!(equisolid < equidistant);
+    }
+}
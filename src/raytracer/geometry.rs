@@ -1,3 +1,5 @@
+use super::bounds::BoundingBox;
+use super::bsdf::Bsdf;
 use super::color::Color;
 use super::ray::Ray;
 use super::vec3::Vec3;
@@ -5,7 +7,96 @@ use super::vec3::Vec3;
 #[derive(Copy, Clone, Debug)]
 pub struct HitInfo {
     pub position: Vec3,
+    // Shading normal, which may be interpolated
     pub normal: Vec3,
+    // Normal of the actual surface
+    pub geometric_normal: Vec3,
+    // Surface coordinates, with the derivatives of the position along them
+    pub uv: (f32, f32),
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    // Width of the surface covered by the sample, zero when unknown
+    pub footprint: f32,
+}
+
+impl Default for HitInfo {
+    fn default() -> HitInfo {
+        HitInfo::new()
+    }
+}
+
+impl HitInfo {
+    pub fn new() -> HitInfo {
+        HitInfo {
+            position: Vec3::zero(),
+            normal: Vec3::zero(),
+            geometric_normal: Vec3::zero(),
+            uv: (0.0, 0.0),
+            dpdu: Vec3::zero(),
+            dpdv: Vec3::zero(),
+            footprint: 0.0,
+        }
+    }
+
+    // For surfaces without parameterization, the derivatives being an arbitrary frame around the normal
+    pub fn set_normal_only(&mut self, normal: Vec3) {
+        let mut unit_normal = normal;
+        unit_normal.normalize();
+
+        let (dpdu, dpdv) = Vec3::orthonormal_basis(&unit_normal);
+
+        self.normal = normal;
+        self.geometric_normal = normal;
+        self.uv = (0.0, 0.0);
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+    }
+
+    // Cosine between a unit direction toward a light and the shading normal, zero when the surface itself
+    // faces away, as perturbed normals may face the light while the surface doesn't
+    pub fn get_diffuse_factor(&self, light_direction: &Vec3) -> f32 {
+        if Vec3::dot_product(light_direction, &self.geometric_normal) > 0.0 {
+            Vec3::dot_product(light_direction, &self.normal).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    // Unit tangent (along u), bitangent (along v) and normal, from the shading normal and the derivatives
+    pub fn get_tangent_frame(&self) -> (Vec3, Vec3, Vec3) {
+        let mut normal = self.normal;
+        normal.normalize();
+
+        let mut tangent = self.dpdu - normal * Vec3::dot_product(&normal, &self.dpdu);
+        if tangent.squared_length() < 1e-12 {
+            let (tangent, bitangent) = Vec3::orthonormal_basis(&normal);
+            return (tangent, bitangent, normal);
+        }
+        tangent.normalize();
+
+        // Keeps the orientation of v, which depends on the parameterization
+        let mut bitangent = Vec3::cross_product(&normal, &tangent);
+        if Vec3::dot_product(&bitangent, &self.dpdv) < 0.0 {
+            bitangent = -bitangent;
+        }
+
+        (tangent, bitangent, normal)
+    }
+}
+
+// Point where a ray goes through the surface of a shape, with the hit there as compute_hit gives it
+// (the normal pointing outward)
+#[derive(Copy, Clone, Debug)]
+pub struct Crossing {
+    pub distance: f32,
+    pub hit_info: HitInfo,
+}
+
+// Part of a ray inside a closed shape
+#[derive(Copy, Clone, Debug)]
+pub struct Interval {
+    pub enter: Crossing,
+    pub exit: Crossing,
 }
 
 pub trait Geometry: Sync + Send {
@@ -16,9 +107,73 @@ pub trait Geometry: Sync + Send {
         exit_dist: Option<&mut f32>,
     ) -> Option<f32>;
 
-    fn get_color(&self, position: &Vec3) -> Color;
+    fn get_color(&self, hit_info: &HitInfo) -> Color;
+
+    // Normal used for shading, which normal and bump maps perturb
+    fn get_shading_normal(&self, hit_info: &HitInfo) -> Vec3 {
+        hit_info.normal
+    }
+
+    // Surfaces without a material are shaded from their color and factors
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Option<Box<dyn Bsdf>> {
+        None
+    }
+
+    // Factors may vary over the surface, as the color
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32>;
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32>;
+
+    // None for unbounded geometry
+    fn get_bounds(&self) -> Option<BoundingBox>;
+
+    // All the parts of the ray inside the shape, sorted and including the ones behind its origin.
+    // Open shapes have no inside and can't be used for CSG.
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        Vec::new()
+    }
+}
+
+// Picks the first hit in front of the ray among the surface crossings (distance and normal), and
+// returns it with the distance where the ray leaves the shape.
+// Like spheres, closed shapes are ignored when the ray starts inside of them.
+pub fn first_hit(hits: &mut [(f32, Vec3)], closed: bool) -> Option<(f32, Vec3, f32)> {
+    hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let index = hits.iter().position(|(distance, _)| *distance >= 0.0)?;
+    if closed && index % 2 == 1 {
+        return None;
+    }
+
+    let (distance, normal) = hits[index];
+    let exit_distance = hits.get(index + 1).map_or(distance, |(exit, _)| *exit);
+
+    Some((distance, normal, exit_distance))
+}
+
+// Pairs the surface crossings of a closed shape, each entering crossing being followed by a leaving one,
+// `get_hit_info` giving the hit at a crossing from its distance and normal.
+// An odd count comes from a ray grazing the shape, which is then considered as missed.
+pub fn pair_intervals(
+    hits: &mut [(f32, Vec3)],
+    get_hit_info: impl Fn(f32, Vec3) -> HitInfo,
+) -> Vec<Interval> {
+    if hits.len() % 2 != 0 {
+        return Vec::new();
+    }
 
-    fn get_reflection_factor(&self) -> Option<f32>;
+    hits.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-    fn get_transparency_factor(&self) -> Option<f32>;
+    hits.chunks_exact(2)
+        .map(|pair| Interval {
+            enter: Crossing {
+                distance: pair[0].0,
+                hit_info: get_hit_info(pair[0].0, pair[0].1),
+            },
+            exit: Crossing {
+                distance: pair[1].0,
+                hit_info: get_hit_info(pair[1].0, pair[1].1),
+            },
+        })
+        .collect()
 }
@@ -0,0 +1,475 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::Geometry;
+use super::geometry::HitInfo;
+use super::image::Image;
+use super::ray::Ray;
+use super::vec3::Vec3;
+use std::io;
+use std::path::Path;
+
+// Terrain made of a grid of height samples, each cell being split into two triangles.
+// The grid starts at `origin`, columns going along x and rows along z.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heightfield {
+    cell_size: f32,
+    color: Color,
+    depth: usize,
+    height_scale: f32,
+    heights: Vec<f32>,
+    max_height: f32,
+    min_height: f32,
+    normals: Vec<Vec3>,
+    origin: Vec3,
+    reflection_factor: Option<f32>,
+    transparency_factor: Option<f32>,
+    width: usize,
+}
+
+impl Heightfield {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        width: usize,
+        depth: usize,
+        heights: Vec<f32>,
+        origin: Vec3,
+        cell_size: f32,
+        height_scale: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Heightfield {
+        assert!(
+            width >= 2 && depth >= 2,
+            "a heightfield needs at least 2x2 samples"
+        );
+        assert_eq!(heights.len(), width * depth);
+
+        let min_height = heights.iter().copied().fold(f32::INFINITY, f32::min);
+        let max_height = heights.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+        let mut heightfield = Heightfield {
+            cell_size,
+            color,
+            depth,
+            height_scale,
+            heights,
+            max_height,
+            min_height,
+            normals: Vec::new(),
+            origin,
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+            width,
+        };
+
+        heightfield.normals = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| heightfield.compute_normal(x, z))
+            .collect();
+
+        heightfield
+    }
+
+    // Heights are read from the image brightness, from 0 to 1, one sample per pixel
+    pub fn from_image(
+        image: &Image,
+        origin: Vec3,
+        cell_size: f32,
+        height_scale: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Heightfield {
+        let heights = image
+            .get_pixels()
+            .iter()
+            .map(|pixel| (pixel.r + pixel.g + pixel.b) / 3.0)
+            .collect();
+
+        Heightfield::new(
+            image.get_width(),
+            image.get_height(),
+            heights,
+            origin,
+            cell_size,
+            height_scale,
+            color,
+            reflection_factor,
+            transparency_factor,
+        )
+    }
+
+    pub fn load_png(
+        path: &Path,
+        origin: Vec3,
+        cell_size: f32,
+        height_scale: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> io::Result<Heightfield> {
+        let image = Image::load_png(path)?;
+
+        Ok(Heightfield::from_image(
+            &image,
+            origin,
+            cell_size,
+            height_scale,
+            color,
+            reflection_factor,
+            transparency_factor,
+        ))
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    // Height of a sample, in world units
+    pub fn get_height(&self, x: usize, z: usize) -> f32 {
+        self.heights[z * self.width + x] * self.height_scale
+    }
+
+    fn get_vertex(&self, x: usize, z: usize) -> Vec3 {
+        self.origin
+            + Vec3::new(
+                x as f32 * self.cell_size,
+                self.get_height(x, z),
+                z as f32 * self.cell_size,
+            )
+    }
+
+    // From the slopes around the sample, one-sided on the borders
+    fn compute_normal(&self, x: usize, z: usize) -> Vec3 {
+        let (left, right) = (x.saturating_sub(1), (x + 1).min(self.width - 1));
+        let (back, front) = (z.saturating_sub(1), (z + 1).min(self.depth - 1));
+
+        let slope_x = (self.get_height(right, z) - self.get_height(left, z))
+            / ((right - left) as f32 * self.cell_size);
+        let slope_z = (self.get_height(x, front) - self.get_height(x, back))
+            / ((front - back) as f32 * self.cell_size);
+
+        let mut normal = Vec3::new(-slope_x, 1.0, -slope_z);
+        normal.normalize();
+
+        normal
+    }
+
+    // Möller-Trumbore, returning the distance and the barycentric coordinates of the hit
+    fn intersect_triangle(ray: &Ray, a: &Vec3, b: &Vec3, c: &Vec3) -> Option<(f32, f32, f32)> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = Vec3::cross_product(ray.get_direction(), &edge2);
+        let determinant = Vec3::dot_product(&edge1, &p);
+        if determinant.abs() < 1e-10 {
+            return None;
+        }
+
+        let inv_determinant = 1.0 / determinant;
+        let to_origin = ray.get_origin() - a;
+
+        let u = Vec3::dot_product(&to_origin, &p) * inv_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = Vec3::cross_product(&to_origin, &edge1);
+        let v = Vec3::dot_product(ray.get_direction(), &q) * inv_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = Vec3::dot_product(&edge2, &q) * inv_determinant;
+        Some((distance, u, v))
+    }
+
+    // Closest hit with the two triangles of a cell, between the given distances,
+    // along with the interpolated normal and the one of the triangle
+    fn intersect_cell(
+        &self,
+        ray: &Ray,
+        x: usize,
+        z: usize,
+        min_distance: f32,
+        max_distance: f32,
+    ) -> Option<(f32, Vec3, Vec3)> {
+        let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+        let vertices = corners.map(|(x, z)| self.get_vertex(x, z));
+        let normals = corners.map(|(x, z)| self.normals[z * self.width + x]);
+
+        let mut closest: Option<(f32, Vec3, Vec3)> = None;
+        for [i, j, k] in [[0, 1, 2], [0, 2, 3]] {
+            let hit =
+                Heightfield::intersect_triangle(ray, &vertices[i], &vertices[j], &vertices[k]);
+
+            if let Some((distance, u, v)) = hit {
+                if distance < min_distance
+                    || distance > max_distance
+                    || closest.is_some_and(|(closest_distance, _, _)| distance >= closest_distance)
+                {
+                    continue;
+                }
+
+                // Interpolated normal, for smooth shading
+                let normal = normals[i] * (1.0 - u - v) + normals[j] * u + normals[k] * v;
+
+                // Both triangles wind so this one points up
+                let geometric_normal =
+                    Vec3::cross_product(&(vertices[k] - vertices[i]), &(vertices[j] - vertices[i]));
+                closest = Some((distance, normal, geometric_normal));
+            }
+        }
+
+        closest
+    }
+}
+
+impl Geometry for Heightfield {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let (enter_distance, exit_distance) = self.get_bounds()?.intersect(ray)?;
+        let mut distance = enter_distance.max(0.0);
+
+        // Walks through the cells crossed by the ray (DDA), from the point where it enters the grid
+        let direction = ray.get_direction();
+        let start = ray.point_at(distance) - self.origin;
+        let last_x = self.width as i64 - 2;
+        let last_z = self.depth as i64 - 2;
+
+        let mut x = ((start.x / self.cell_size).floor() as i64).clamp(0, last_x);
+        let mut z = ((start.z / self.cell_size).floor() as i64).clamp(0, last_z);
+
+        let step_x: i64 = if direction.x >= 0.0 { 1 } else { -1 };
+        let step_z: i64 = if direction.z >= 0.0 { 1 } else { -1 };
+
+        let delta_x = (self.cell_size / direction.x).abs();
+        let delta_z = (self.cell_size / direction.z).abs();
+
+        let next_boundary =
+            |cell: i64, step: i64| (cell + (step > 0) as i64) as f32 * self.cell_size;
+        let mut next_x = if direction.x != 0.0 {
+            distance + (next_boundary(x, step_x) - start.x) / direction.x
+        } else {
+            f32::INFINITY
+        };
+        let mut next_z = if direction.z != 0.0 {
+            distance + (next_boundary(z, step_z) - start.z) / direction.z
+        } else {
+            f32::INFINITY
+        };
+
+        // Tolerance so hits on the border between two cells aren't missed
+        let epsilon = 1e-4 * self.cell_size;
+
+        loop {
+            let cell_exit = next_x.min(next_z).min(exit_distance);
+
+            if let Some((hit_distance, normal, geometric_normal)) = self.intersect_cell(
+                ray,
+                x as usize,
+                z as usize,
+                distance - epsilon,
+                cell_exit + epsilon,
+            ) {
+                if hit_distance >= 0.0 {
+                    // A heightfield has no thickness
+                    if let Some(exit_dist) = exit_dist {
+                        *exit_dist = hit_distance
+                    }
+
+                    if let Some(hit_info) = hitinfo {
+                        hit_info.position = ray.point_at(hit_distance);
+                        hit_info.normal = normal;
+                        hit_info.geometric_normal = geometric_normal;
+
+                        // u goes along x and v against z, over the whole grid
+                        let size_x = (self.width - 1) as f32 * self.cell_size;
+                        let size_z = (self.depth - 1) as f32 * self.cell_size;
+                        let local = hit_info.position - self.origin;
+                        hit_info.uv = (local.x / size_x, 1.0 - local.z / size_z);
+
+                        // Following the slopes of the triangle
+                        let slope_x = -geometric_normal.x / geometric_normal.y;
+                        let slope_z = -geometric_normal.z / geometric_normal.y;
+                        hit_info.dpdu = Vec3::new(size_x, slope_x * size_x, 0.0);
+                        hit_info.dpdv = Vec3::new(0.0, -slope_z * size_z, -size_z);
+                    }
+
+                    return Some(hit_distance);
+                }
+            }
+
+            if cell_exit >= exit_distance {
+                return None;
+            }
+
+            if next_x < next_z {
+                x += step_x;
+                distance = next_x;
+                next_x += delta_x;
+            } else {
+                z += step_z;
+                distance = next_z;
+                next_z += delta_z;
+            }
+
+            if x < 0 || x > last_x || z < 0 || z > last_z {
+                return None;
+            }
+        }
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.color
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let (low, high) = (
+            self.min_height * self.height_scale,
+            self.max_height * self.height_scale,
+        );
+
+        Some(BoundingBox::new(
+            self.origin + Vec3::new(0.0, low.min(high), 0.0),
+            self.origin
+                + Vec3::new(
+                    (self.width - 1) as f32 * self.cell_size,
+                    low.max(high),
+                    (self.depth - 1) as f32 * self.cell_size,
+                ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_heightfield(rng: &mut rand::XorShiftRng) -> Heightfield {
+        let (width, depth) = (7, 5);
+        let heights = (0..width * depth).map(|_| rng.next_f32()).collect();
+
+        Heightfield::new(
+            width,
+            depth,
+            heights,
+            Vec3::new(-1.0, 0.5, 2.0),
+            0.5,
+            1.5,
+            Color::black(),
+            0.0,
+            0.0,
+        )
+    }
+
+    // Closest hit among all the triangles of the grid
+    fn brute_force(heightfield: &Heightfield, ray: &Ray) -> Option<f32> {
+        (0..heightfield.depth - 1)
+            .flat_map(|z| (0..heightfield.width - 1).map(move |x| (x, z)))
+            .filter_map(|(x, z)| heightfield.intersect_cell(ray, x, z, 0.0, f32::INFINITY))
+            .map(|(distance, _, _)| distance)
+            .fold(None, |closest: Option<f32>, distance| {
+                Some(closest.map_or(distance, |closest| closest.min(distance)))
+            })
+    }
+
+    #[test]
+    fn traversal_finds_the_closest_triangle() {
+        let mut rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+        let heightfield = random_heightfield(&mut rng);
+        let mut hits = 0;
+
+        for _ in 0..5000 {
+            // Rays from around the grid, inside its bounds as well, in any direction
+            let origin = Vec3::new(
+                rng.next_f32() * 5.0 - 2.5,
+                rng.next_f32() * 3.0,
+                rng.next_f32() * 4.0 + 1.0,
+            );
+            let direction = Vec3::new(
+                rng.next_f32() * 2.0 - 1.0,
+                rng.next_f32() * 2.0 - 1.0,
+                rng.next_f32() * 2.0 - 1.0,
+            );
+            let ray = Ray::new(origin, direction);
+
+            let expected = brute_force(&heightfield, &ray);
+            let distance = heightfield.compute_hit(&ray, None, None);
+
+            match (distance, expected) {
+                (Some(distance), Some(expected)) => {
+                    assert!((distance - expected).abs() < 1e-4, "{:?}", ray);
+                    hits += 1;
+                }
+                (None, None) => {}
+                _ => panic!("{:?}: {:?} instead of {:?}", ray, distance, expected),
+            }
+        }
+
+        assert!(hits > 500, "{}", hits);
+    }
+
+    #[test]
+    fn flat_heightfield_is_hit_from_above() {
+        let heightfield = Heightfield::new(
+            3,
+            3,
+            vec![0.5; 9],
+            Vec3::zero(),
+            1.0,
+            2.0,
+            Color::black(),
+            0.0,
+            0.0,
+        );
+
+        let mut hit_info = HitInfo::new();
+        let distance = heightfield
+            .compute_hit(
+                &Ray::new(Vec3::new(0.7, 5.0, 1.2), Vec3::new(0.0, -1.0, 0.0)),
+                Some(&mut hit_info),
+                None,
+            )
+            .unwrap();
+
+        assert!((distance - 4.0).abs() < 1e-5);
+        assert!((hit_info.normal.y - 1.0).abs() < 1e-5);
+        assert!(hit_info.geometric_normal.y > 0.0);
+
+        // Slanted rays cross cells before reaching the surface
+        let ray = Ray::new(Vec3::new(-1.0, 2.0, -1.0), Vec3::new(1.0, -0.5, 1.0));
+        let distance = heightfield.compute_hit(&ray, None, None).unwrap();
+        assert!((ray.point_at(distance).y - 1.0).abs() < 1e-5);
+
+        // Nothing but empty space beside it
+        let ray = Ray::new(Vec3::new(3.0, 5.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(heightfield.compute_hit(&ray, None, None).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+use super::color::Color;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+// Inverse of the sRGB transfer function, for images storing gamma encoded colors
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Source pixels covered by a pixel of a smaller size, weighted by how much of them it covers
+fn box_weights(index: usize, size: usize, source_size: usize) -> Vec<(usize, f32)> {
+    let ratio = source_size as f32 / size as f32;
+    let (start, end) = (index as f32 * ratio, (index + 1) as f32 * ratio);
+
+    (start.floor() as usize..(end.ceil() as usize).min(source_size))
+        .map(|source| {
+            let covered = end.min(source as f32 + 1.0) - start.max(source as f32);
+            (source, covered / ratio)
+        })
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect()
+}
+
+// Image with floating point colors, from 0 to 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Image {
+        assert_eq!(pixels.len(), width * height);
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // Values are kept as stored in the file, alpha is ignored
+    pub fn load_png(path: &Path) -> io::Result<Image> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND);
+
+        let (info, mut reader) = decoder.read_info()?;
+
+        let mut data = vec![0u8; info.buffer_size()];
+        reader.next_frame(&mut data)?;
+
+        let sample_size = match info.bit_depth {
+            png::BitDepth::Sixteen => 2,
+            _ => 1,
+        };
+
+        let read_sample = |offset: usize| -> f32 {
+            if sample_size == 2 {
+                u16::from_be_bytes([data[offset], data[offset + 1]]) as f32 / 65535.0
+            } else {
+                data[offset] as f32 / 255.0
+            }
+        };
+
+        let channel_count = info.color_type.samples();
+        let width = info.width as usize;
+        let height = info.height as usize;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let offset = y * info.line_size + x * channel_count * sample_size;
+                let color = match info.color_type {
+                    png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                        let value = read_sample(offset);
+                        Color {
+                            r: value,
+                            g: value,
+                            b: value,
+                        }
+                    }
+                    _ => Color {
+                        r: read_sample(offset),
+                        g: read_sample(offset + sample_size),
+                        b: read_sample(offset + 2 * sample_size),
+                    },
+                };
+
+                pixels.push(color);
+            }
+        }
+
+        Ok(Image::new(width, height, pixels))
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn get_pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    // Same image with sRGB values turned into linear ones
+    pub fn decode_srgb(&self) -> Image {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|pixel| Color {
+                r: srgb_to_linear(pixel.r),
+                g: srgb_to_linear(pixel.g),
+                b: srgb_to_linear(pixel.b),
+            })
+            .collect();
+
+        Image::new(self.width, self.height, pixels)
+    }
+
+    // Image half as large, each pixel averaging the block it covers. Odd sizes give blocks of one and a half
+    // pixels, so the last row and column are weighted in rather than dropped.
+    pub fn downsample(&self) -> Image {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+
+        let columns: Vec<_> = (0..width)
+            .map(|x| box_weights(x, width, self.width))
+            .collect();
+        let rows: Vec<_> = (0..height)
+            .map(|y| box_weights(y, height, self.height))
+            .collect();
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in rows.iter() {
+            for column in columns.iter() {
+                let mut color = Color::black();
+                for &(y, weight_y) in row.iter() {
+                    for &(x, weight_x) in column.iter() {
+                        color = color + self.get_pixel(x, y) * (weight_x * weight_y);
+                    }
+                }
+
+                pixels.push(color);
+            }
+        }
+
+        Image::new(width, height, pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grey(value: f32) -> Color {
+        Color::new(value, value, value)
+    }
+
+    #[test]
+    fn downsampling_averages_blocks() {
+        let image = Image::new(
+            4,
+            2,
+            [1.0, 3.0, 0.0, 0.0, 3.0, 1.0, 2.0, 6.0]
+                .iter()
+                .map(|value| grey(*value))
+                .collect(),
+        );
+
+        let level = image.downsample();
+        assert_eq!((level.get_width(), level.get_height()), (2, 1));
+        assert!((level.get_pixel(0, 0).r - 2.0).abs() < 1e-6);
+        assert!((level.get_pixel(1, 0).r - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn downsampling_odd_sizes_keeps_the_edges() {
+        // Three columns become one averaging all of them
+        let image = Image::new(3, 1, vec![grey(0.0), grey(0.0), grey(3.0)]);
+        let level = image.downsample();
+        assert_eq!((level.get_width(), level.get_height()), (1, 1));
+        assert!((level.get_pixel(0, 0).r - 1.0).abs() < 1e-6);
+
+        // The middle pixel of five is split between both halves
+        let image = Image::new(1, 5, (0..5).map(|value| grey(value as f32)).collect());
+        let level = image.downsample();
+        assert_eq!((level.get_width(), level.get_height()), (1, 2));
+        assert!((level.get_pixel(0, 0).r - 0.8).abs() < 1e-6);
+        assert!((level.get_pixel(0, 1).r - 3.2).abs() < 1e-6);
+
+        // The mean color is kept, whatever the size
+        let image = Image::new(
+            5,
+            3,
+            (0..15).map(|value| grey((value * 7 % 11) as f32)).collect(),
+        );
+        let mean = |image: &Image| {
+            image.get_pixels().iter().map(|pixel| pixel.r).sum::<f32>()
+                / image.get_pixels().len() as f32
+        };
+        assert!((mean(&image.downsample()) - mean(&image)).abs() < 1e-4);
+    }
+}
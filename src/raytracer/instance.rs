@@ -0,0 +1,255 @@
+use super::bounds::BoundingBox;
+use super::bsdf::Bsdf;
+use super::color::Color;
+use super::geometry::{Crossing, Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::transform::Transform;
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+// Places a shared geometry in the world, rays being brought to the object space of the geometry
+#[derive(Clone)]
+pub struct Instance {
+    geometry: Arc<dyn Geometry>,
+    transform: Transform,
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<dyn Geometry>, transform: Transform) -> Instance {
+        Instance {
+            geometry,
+            transform,
+        }
+    }
+
+    pub fn get_geometry(&self) -> &Arc<dyn Geometry> {
+        &self.geometry
+    }
+
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    // Ray in object space, with the factor to bring object space distances back to world space
+    fn object_ray(&self, ray: &Ray) -> Option<(Ray, f32)> {
+        let origin = self.transform.inverse_transform_point(ray.get_origin());
+        let direction = self.transform.inverse_transform_vector(ray.get_direction());
+
+        // Object space rays are normalized, so distances have to be scaled back to world space
+        let scale = direction.length();
+        if scale == 0.0 {
+            return None;
+        }
+
+        Some((
+            Ray::new(origin, direction)
+                .with_time(ray.get_time())
+                .with_footprint(ray.get_footprint(0.0) * scale, ray.get_spread()),
+            scale,
+        ))
+    }
+
+    // Hit brought to object space, where textures and maps are evaluated
+    fn object_hit_info(&self, hit_info: &HitInfo) -> HitInfo {
+        // The footprint is measured along the surface to follow the scaling
+        let tangent_length = hit_info.dpdu.length();
+        let footprint = if tangent_length > 0.0 {
+            hit_info.footprint
+                * self
+                    .transform
+                    .inverse_transform_vector(&hit_info.dpdu)
+                    .length()
+                / tangent_length
+        } else {
+            hit_info.footprint
+        };
+
+        HitInfo {
+            position: self.transform.inverse_transform_point(&hit_info.position),
+            normal: self.transform.inverse_transform_normal(&hit_info.normal),
+            geometric_normal: self
+                .transform
+                .inverse_transform_normal(&hit_info.geometric_normal),
+            uv: hit_info.uv,
+            dpdu: self.transform.inverse_transform_vector(&hit_info.dpdu),
+            dpdv: self.transform.inverse_transform_vector(&hit_info.dpdv),
+            footprint,
+        }
+    }
+
+    // Brings an object space hit to world space, where it is at `position`
+    fn to_world(&self, hit_info: &mut HitInfo, position: Vec3) {
+        hit_info.position = position;
+        hit_info.normal = self.transform.transform_normal(&hit_info.normal);
+        hit_info.geometric_normal = self.transform.transform_normal(&hit_info.geometric_normal);
+        hit_info.dpdu = self.transform.transform_vector(&hit_info.dpdu);
+        hit_info.dpdv = self.transform.transform_vector(&hit_info.dpdv);
+    }
+}
+
+impl Geometry for Instance {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let (object_ray, scale) = self.object_ray(ray)?;
+
+        // The exit distance is only asked for when needed, as some shapes have to march to find it
+        let mut object_exit_dist = 0f32;
+        let object_exit = if exit_dist.is_some() {
+            Some(&mut object_exit_dist)
+        } else {
+            None
+        };
+
+        let object_dist = match hitinfo {
+            Some(hit_info) => {
+                let distance =
+                    self.geometry
+                        .compute_hit(&object_ray, Some(&mut *hit_info), object_exit)?;
+
+                self.to_world(hit_info, ray.point_at(distance / scale));
+
+                distance
+            }
+            None => self.geometry.compute_hit(&object_ray, None, object_exit)?,
+        };
+
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = object_exit_dist / scale;
+        }
+
+        Some(object_dist / scale)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.geometry.get_color(&self.object_hit_info(hit_info))
+    }
+
+    fn get_shading_normal(&self, hit_info: &HitInfo) -> Vec3 {
+        let mut normal = self.transform.transform_normal(
+            &self
+                .geometry
+                .get_shading_normal(&self.object_hit_info(hit_info)),
+        );
+        normal.normalize();
+
+        normal
+    }
+
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Option<Box<dyn Bsdf>> {
+        self.geometry.get_bsdf(&self.object_hit_info(hit_info))
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.geometry
+            .get_reflection_factor(&self.object_hit_info(hit_info))
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.geometry
+            .get_transparency_factor(&self.object_hit_info(hit_info))
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.geometry
+            .get_bounds()
+            .map(|bounds| bounds.transform(&self.transform))
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        let (object_ray, scale) = match self.object_ray(ray) {
+            Some(object_ray) => object_ray,
+            None => return Vec::new(),
+        };
+
+        let to_world = |crossing: Crossing| {
+            let distance = crossing.distance / scale;
+            let mut hit_info = crossing.hit_info;
+            self.to_world(&mut hit_info, ray.point_at(distance));
+
+            Crossing { distance, hit_info }
+        };
+
+        self.geometry
+            .get_intervals(&object_ray)
+            .into_iter()
+            .map(|interval| Interval {
+                enter: to_world(interval.enter),
+                exit: to_world(interval.exit),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::sphere::Sphere;
+
+    // Unit sphere stretched into the ellipsoid x²/4 + y² + 4z² = 1
+    fn ellipsoid() -> Instance {
+        Instance::new(
+            Arc::new(Sphere::new(Vec3::zero(), 1.0, Color::black(), 0.0, 0.0)),
+            Transform::scale(Vec3::new(2.0, 1.0, 0.5)),
+        )
+    }
+
+    fn normalized(vector: Vec3) -> Vec3 {
+        let mut vector = vector;
+        vector.normalize();
+
+        vector
+    }
+
+    #[test]
+    fn scaled_sphere_is_an_ellipsoid() {
+        let ellipsoid = ellipsoid();
+
+        // Along each axis, the distance to the surface follows its scale
+        for (origin, enter, exit) in [
+            (Vec3::new(5.0, 0.0, 0.0), 3.0, 7.0),
+            (Vec3::new(0.0, 5.0, 0.0), 4.0, 6.0),
+            (Vec3::new(0.0, 0.0, 5.0), 4.5, 5.5),
+        ] {
+            let ray = Ray::new(origin, -origin);
+            let mut hit_info = HitInfo::new();
+            let mut exit_dist = 0f32;
+
+            let distance = ellipsoid
+                .compute_hit(&ray, Some(&mut hit_info), Some(&mut exit_dist))
+                .unwrap();
+
+            assert!((distance - enter).abs() < 1e-4, "{} {}", distance, enter);
+            assert!((exit_dist - exit).abs() < 1e-4, "{} {}", exit_dist, exit);
+            assert!((normalized(hit_info.normal) - normalized(origin)).length() < 1e-4);
+            assert!((hit_info.position - ray.point_at(enter)).length() < 1e-4);
+        }
+
+        // Off the axes, the normal is the gradient of the implicit equation
+        let ray = Ray::new(Vec3::new(5.0, 0.3, 0.2), Vec3::new(-1.0, 0.0, 0.0));
+        let mut hit_info = HitInfo::new();
+        let distance = ellipsoid
+            .compute_hit(&ray, Some(&mut hit_info), None)
+            .unwrap();
+
+        let x = 2.0 * (1.0f32 - 0.3 * 0.3 - 4.0 * 0.2 * 0.2).sqrt();
+        assert!((distance - (5.0 - x)).abs() < 1e-4);
+
+        let expected = normalized(Vec3::new(x / 4.0, 0.3, 4.0 * 0.2));
+        assert!((normalized(hit_info.normal) - expected).length() < 1e-4);
+        assert!((ellipsoid.get_shading_normal(&hit_info) - expected).length() < 1e-4);
+
+        // Distances without a hit info are scaled the same way
+        assert!((ellipsoid.compute_hit(&ray, None, None).unwrap() - distance).abs() < 1e-6);
+        assert!(ellipsoid
+            .compute_hit(
+                &Ray::new(Vec3::new(5.0, 1.1, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+                None,
+                None
+            )
+            .is_none());
+    }
+}
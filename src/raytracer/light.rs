@@ -4,6 +4,8 @@ use super::ray::Ray;
 use super::scene::Scene;
 use super::vec3::Vec3;
 
+// Superseded by directional_light::DirectionalLight, kept as it is part of the public API
+#[allow(dead_code)]
 #[derive(Copy, Clone, Debug)]
 pub struct DirectionalLight {
     color: (f32, f32, f32),
@@ -12,4 +14,13 @@ pub struct DirectionalLight {
 
 pub trait Light: Sync + Send {
     fn compute_light(&self, scene: &Scene, hit_info: &HitInfo, pixel_color: &mut Color, ray: &Ray);
+
+    // Unit direction toward the light from a point, with the light reaching it (None when in shadow),
+    // for surfaces shaded by a BSDF
+    fn get_incident_light(
+        &self,
+        scene: &Scene,
+        position: &Vec3,
+        time: f32,
+    ) -> Option<(Vec3, Color)>;
 }
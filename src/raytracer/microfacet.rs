@@ -0,0 +1,786 @@
+use super::bsdf::{Bsdf, BsdfSample, Material};
+use super::color::Color;
+use super::geometry::HitInfo;
+use super::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Directions below are in the tangent frame of the hit, z being along the normal
+
+pub fn reflect(direction: &Vec3, normal: &Vec3) -> Vec3 {
+    2.0 * Vec3::dot_product(direction, normal) * normal - direction
+}
+
+// Direction going through the surface, `eta` being the index of refraction of the side of `direction`
+// over the one of the other side, None for total internal reflection
+fn refract(direction: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = Vec3::dot_product(direction, normal);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i).max(0.0);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-direction * eta + normal * (eta * cos_i - cos_t))
+}
+
+// Fraction of light reflected by a dielectric, `eta` being the index of refraction of the other side
+// over the one of the incident side
+pub fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+
+    (parallel * parallel + perpendicular * perpendicular) / 2.0
+}
+
+// Fraction of light reflected by a metal of complex index of refraction eta + i k, for one wavelength
+fn fresnel_conductor_channel(cos_i: f32, eta: f32, k: f32) -> f32 {
+    let cos2 = cos_i.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = ((a2_plus_b2 + t0) / 2.0).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * a * cos_i;
+    let perpendicular = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let parallel = perpendicular * (t3 - t4) / (t3 + t4);
+
+    (perpendicular + parallel) / 2.0
+}
+
+// Approximation of the reflected fraction from the one at normal incidence
+pub fn fresnel_schlick(cos_i: f32, f0: &Color) -> Color {
+    let weight = (1.0 - cos_i.clamp(0.0, 1.0)).powi(5);
+
+    Color::new(
+        f0.r + (1.0 - f0.r) * weight,
+        f0.g + (1.0 - f0.g) * weight,
+        f0.b + (1.0 - f0.b) * weight,
+    )
+}
+
+pub fn fresnel_conductor(cos_i: f32, eta: &Color, k: &Color) -> Color {
+    Color::new(
+        fresnel_conductor_channel(cos_i, eta.r, k.r),
+        fresnel_conductor_channel(cos_i, eta.g, k.g),
+        fresnel_conductor_channel(cos_i, eta.b, k.b),
+    )
+}
+
+const ALBEDO_TABLE_SIZE: usize = 32;
+
+// GGX (Trowbridge-Reitz) distribution of microfacet normals, isotropic
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GgxDistribution {
+    alpha: f32,
+}
+
+impl GgxDistribution {
+    // Roughness goes from 0 (mirror) to 1, and is squared for a perceptually even scale
+    pub fn new(roughness: f32) -> GgxDistribution {
+        GgxDistribution {
+            alpha: (roughness * roughness).max(1e-3),
+        }
+    }
+
+    pub fn get_alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    // Density of microfacet normals
+    pub fn d(&self, normal: &Vec3) -> f32 {
+        if normal.z <= 0.0 {
+            return 0.0;
+        }
+
+        let alpha2 = self.alpha * self.alpha;
+        let denominator = normal.z * normal.z * (alpha2 - 1.0) + 1.0;
+
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    fn lambda(&self, direction: &Vec3) -> f32 {
+        let cos2 = direction.z * direction.z;
+        if cos2 <= 0.0 {
+            return f32::INFINITY;
+        }
+
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    // Fraction of the microfacets visible from a direction
+    pub fn g1(&self, direction: &Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(direction))
+    }
+
+    // Fraction of the microfacets visible from both directions (height-correlated Smith)
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Microfacet normal picked among the ones visible from wo, which has to be above the surface (Heitz 2018)
+    pub fn sample_visible_normal(&self, wo: &Vec3, u1: f32, u2: f32) -> Vec3 {
+        // Stretched so the distribution becomes a hemisphere
+        let mut view = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z);
+        view.normalize();
+
+        let length2 = view.x * view.x + view.y * view.y;
+        let t1 = if length2 > 0.0 {
+            Vec3::new(-view.y, view.x, 0.0) * (1.0 / length2.sqrt())
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = Vec3::cross_product(&view, &t1);
+
+        // Point on the projected half disk visible from the view
+        let radius = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = radius * phi.cos();
+        let s = 0.5 * (1.0 + view.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * radius * phi.sin();
+
+        let hemisphere = t1 * p1 + t2 * p2 + view * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        let mut normal = Vec3::new(
+            self.alpha * hemisphere.x,
+            self.alpha * hemisphere.y,
+            hemisphere.z.max(1e-6),
+        );
+        normal.normalize();
+
+        normal
+    }
+}
+
+// Fraction of light a microfacet BSDF keeps after a single scattering, by cosine of the view direction,
+// to give back what is lost to microfacets shadowing each other (energy compensation)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlbedoTable {
+    values: Arc<Vec<f32>>,
+}
+
+impl AlbedoTable {
+    // `weight` gives the sample weight of a visible microfacet normal, for a view direction above the surface.
+    // View directions are regularly spread in cosine, and normals stratified.
+    pub fn new(
+        distribution: &GgxDistribution,
+        weight: impl Fn(&Vec3, &Vec3) -> f32,
+    ) -> AlbedoTable {
+        const STRATA: usize = 16;
+
+        let values = (0..ALBEDO_TABLE_SIZE)
+            .map(|i| {
+                let cos_o = (i as f32 + 0.5) / ALBEDO_TABLE_SIZE as f32;
+                let wo = Vec3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+
+                let mut total = 0.0;
+                for a in 0..STRATA {
+                    for b in 0..STRATA {
+                        let u1 = (a as f32 + 0.5) / STRATA as f32;
+                        let u2 = (b as f32 + 0.5) / STRATA as f32;
+
+                        total += weight(&wo, &distribution.sample_visible_normal(&wo, u1, u2));
+                    }
+                }
+
+                total / (STRATA * STRATA) as f32
+            })
+            .collect();
+
+        AlbedoTable {
+            values: Arc::new(values),
+        }
+    }
+
+    pub fn get_albedo(&self, cos_o: f32) -> f32 {
+        let position = (cos_o.clamp(0.0, 1.0) * ALBEDO_TABLE_SIZE as f32 - 0.5)
+            .clamp(0.0, (ALBEDO_TABLE_SIZE - 1) as f32);
+        let index = (position as usize).min(ALBEDO_TABLE_SIZE - 2);
+        let factor = position - index as f32;
+
+        self.values[index] * (1.0 - factor) + self.values[index + 1] * factor
+    }
+}
+
+// Rough metal, with light only reflected.
+// Light lost by microfacets shadowing each other is given back (energy compensation), so rough metals
+// don't get darker than polished ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conductor {
+    albedo: AlbedoTable,
+    distribution: GgxDistribution,
+    // Complex index of refraction, for red, green and blue
+    eta: Color,
+    k: Color,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f32) -> Conductor {
+        let distribution = GgxDistribution::new(roughness);
+
+        // Without Fresnel, which is accounted for by the compensation
+        let albedo = AlbedoTable::new(&distribution, |wo, normal| {
+            let wi = reflect(wo, normal);
+            if wi.z > 0.0 {
+                distribution.g(wo, &wi) / distribution.g1(wo)
+            } else {
+                0.0
+            }
+        });
+
+        Conductor {
+            albedo,
+            distribution,
+            eta,
+            k,
+        }
+    }
+
+    pub fn gold(roughness: f32) -> Conductor {
+        Conductor::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f32) -> Conductor {
+        Conductor::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f32) -> Conductor {
+        Conductor::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f32) -> Conductor {
+        Conductor::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    pub fn get_distribution(&self) -> &GgxDistribution {
+        &self.distribution
+    }
+
+    // Color of the metal seen straight on
+    pub fn get_reflectance(&self) -> Color {
+        fresnel_conductor(1.0, &self.eta, &self.k)
+    }
+
+    // Scale for the energy lost to multiple scattering, larger for rougher and more reflective metals
+    fn get_compensation(&self, cos_o: f32) -> Color {
+        let missing = 1.0 / self.albedo.get_albedo(cos_o) - 1.0;
+        let reflectance = self.get_reflectance();
+
+        Color::new(
+            1.0 + reflectance.r * missing,
+            1.0 + reflectance.g * missing,
+            1.0 + reflectance.b * missing,
+        )
+    }
+}
+
+impl Bsdf for Conductor {
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::black();
+        }
+
+        let mut half = wo + wi;
+        half.normalize();
+
+        let fresnel = fresnel_conductor(Vec3::dot_product(wi, &half), &self.eta, &self.k);
+        let specular =
+            self.distribution.d(&half) * self.distribution.g(wo, wi) / (4.0 * wo.z * wi.z);
+
+        fresnel * self.get_compensation(wo.z) * specular
+    }
+
+    fn sample(&self, rng: &mut rand::XorShiftRng, wo: &Vec3) -> Option<BsdfSample> {
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let normal = self
+            .distribution
+            .sample_visible_normal(wo, rng.next_f32(), rng.next_f32());
+        let wi = reflect(wo, &normal);
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        let fresnel = fresnel_conductor(Vec3::dot_product(wo, &normal), &self.eta, &self.k);
+        let shadowing = self.distribution.g(wo, &wi) / self.distribution.g1(wo);
+
+        Some(BsdfSample {
+            direction: wi,
+            weight: fresnel * self.get_compensation(wo.z) * shadowing,
+        })
+    }
+}
+
+impl Material for Conductor {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf> {
+        Box::new(self.clone())
+    }
+}
+
+// Rough glass, reflecting or refracting light through microfacets, as frosted glass.
+// The outside is on the side of the normal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoughDielectric {
+    // For light arriving from the outside, and from the inside, None without energy compensation
+    albedo: Option<[AlbedoTable; 2]>,
+    distribution: GgxDistribution,
+    // Index of refraction of the inside
+    ior: f32,
+    // Scale of the light going through
+    transmittance: Color,
+}
+
+// Reflection or refraction of wo (above the surface) on a microfacet, `eta` being the ratio of the index of
+// refraction beyond the surface over the one of the side of wo.
+// None when there's no such direction on the side it should be (above for reflections, below for refractions).
+fn scatter(wo: &Vec3, normal: &Vec3, eta: f32, is_reflection: bool) -> Option<Vec3> {
+    let wi = if is_reflection {
+        reflect(wo, normal)
+    } else {
+        refract(wo, normal, 1.0 / eta)?
+    };
+
+    if is_reflection == (wi.z > 0.0) {
+        Some(wi)
+    } else {
+        None
+    }
+}
+
+impl RoughDielectric {
+    pub fn new(ior: f32, roughness: f32) -> RoughDielectric {
+        let distribution = GgxDistribution::new(roughness);
+
+        // Both reflections and refractions, each one being weighted by its probability
+        let albedo = [ior, 1.0 / ior].map(|eta| {
+            AlbedoTable::new(&distribution, |wo, normal| {
+                let fresnel = fresnel_dielectric(Vec3::dot_product(wo, normal), eta);
+                let shadowing = |is_reflection: bool| {
+                    scatter(wo, normal, eta, is_reflection)
+                        .map_or(0.0, |wi| distribution.g(wo, &wi) / distribution.g1(wo))
+                };
+
+                fresnel * shadowing(true) + (1.0 - fresnel) * shadowing(false)
+            })
+        });
+
+        RoughDielectric {
+            albedo: Some(albedo),
+            ..RoughDielectric::single_scattering(ior, roughness)
+        }
+    }
+
+    // Without energy compensation, so rough glass gets a little darker, but cheap enough to be made at each hit
+    pub fn single_scattering(ior: f32, roughness: f32) -> RoughDielectric {
+        RoughDielectric {
+            albedo: None,
+            distribution: GgxDistribution::new(roughness),
+            ior,
+            transmittance: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    fn get_compensation(&self, wo: &Vec3) -> f32 {
+        let table = if wo.z > 0.0 { 0 } else { 1 };
+        self.albedo
+            .as_ref()
+            .map_or(1.0, |albedo| 1.0 / albedo[table].get_albedo(wo.z.abs()))
+    }
+
+    pub fn with_transmittance(self, transmittance: Color) -> RoughDielectric {
+        RoughDielectric {
+            transmittance,
+            ..self
+        }
+    }
+
+    pub fn get_distribution(&self) -> &GgxDistribution {
+        &self.distribution
+    }
+
+    pub fn get_ior(&self) -> f32 {
+        self.ior
+    }
+}
+
+impl Bsdf for RoughDielectric {
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z == 0.0 || wi.z == 0.0 {
+            return Color::black();
+        }
+
+        // Ratio of the index of refraction beyond the surface from wo over the one on its side
+        let eta = if wo.z > 0.0 { self.ior } else { 1.0 / self.ior };
+        let compensation = self.get_compensation(wo);
+
+        // Microfacets have to face both directions from their side
+        let is_visible = |half: &Vec3| {
+            Vec3::dot_product(wo, half) * wo.z > 0.0 && Vec3::dot_product(wi, half) * wi.z > 0.0
+        };
+
+        if wo.z * wi.z > 0.0 {
+            let mut half = wo + wi;
+            half.normalize();
+            if half.z < 0.0 {
+                half = -half;
+            }
+            if !is_visible(&half) {
+                return Color::black();
+            }
+
+            let fresnel = fresnel_dielectric(Vec3::dot_product(wo, &half).abs(), eta);
+            let reflection = self.distribution.d(&half) * self.distribution.g(wo, wi) * fresnel
+                / (4.0 * wo.z.abs() * wi.z.abs());
+
+            return Color::new(1.0, 1.0, 1.0) * (reflection * compensation);
+        }
+
+        // Generalized half vector of the refraction (Walter et al. 2007)
+        let mut half = wo + *wi * eta;
+        half.normalize();
+        if half.z < 0.0 {
+            half = -half;
+        }
+
+        if !is_visible(&half) {
+            return Color::black();
+        }
+
+        let cos_o = Vec3::dot_product(wo, &half);
+        let cos_i = Vec3::dot_product(wi, &half);
+
+        // Radiance isn't scaled by the change of medium, as for sampled refractions
+        let fresnel = fresnel_dielectric(cos_o.abs(), eta);
+        let denominator = cos_o + eta * cos_i;
+        let transmission = self.distribution.d(&half)
+            * self.distribution.g(wo, wi)
+            * (1.0 - fresnel)
+            * eta
+            * eta
+            * (cos_o * cos_i).abs()
+            / (wo.z.abs() * wi.z.abs() * denominator * denominator);
+
+        self.transmittance * (transmission * compensation)
+    }
+
+    fn sample(&self, rng: &mut rand::XorShiftRng, wo: &Vec3) -> Option<BsdfSample> {
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        // Sampled as if wo was outside, and flipped back after
+        let entering = wo.z > 0.0;
+        let side = if entering { 1.0 } else { -1.0 };
+        let wo_above = *wo * side;
+        let eta = if entering { self.ior } else { 1.0 / self.ior };
+
+        let normal =
+            self.distribution
+                .sample_visible_normal(&wo_above, rng.next_f32(), rng.next_f32());
+        let cos_o = Vec3::dot_product(&wo_above, &normal);
+
+        // Reflection or refraction is picked with the Fresnel factor as probability, so it cancels out
+        let is_reflection = rng.next_f32() < fresnel_dielectric(cos_o, eta);
+        let wi_above = scatter(&wo_above, &normal, eta, is_reflection)?;
+        let transmittance = if is_reflection {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            self.transmittance
+        };
+
+        let shadowing = self.distribution.g(&wo_above, &wi_above) / self.distribution.g1(&wo_above);
+
+        Some(BsdfSample {
+            direction: wi_above * side,
+            weight: transmittance * (shadowing * self.get_compensation(wo)),
+        })
+    }
+
+    fn has_transmission(&self) -> bool {
+        true
+    }
+}
+
+impl Material for RoughDielectric {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn rng() -> rand::XorShiftRng {
+        rand::XorShiftRng::from_seed([1, 2, 3, 4])
+    }
+
+    fn direction(cos_theta: f32) -> Vec3 {
+        Vec3::new((1.0 - cos_theta * cos_theta).sqrt(), 0.0, cos_theta)
+    }
+
+    // Direction spread evenly over the whole sphere
+    fn uniform_sphere(rng: &mut rand::XorShiftRng) -> Vec3 {
+        let z = 1.0 - 2.0 * rng.next_f32();
+        let phi = 2.0 * PI * rng.next_f32();
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+
+        Vec3::new(radius * phi.cos(), radius * phi.sin(), z)
+    }
+
+    // Light reflected toward wo under a uniform white environment, from the sampled directions
+    fn sampled_albedo(bsdf: &dyn Bsdf, wo: &Vec3, count: usize) -> f32 {
+        let mut rng = rng();
+        let total: f32 = (0..count)
+            .filter_map(|_| bsdf.sample(&mut rng, wo))
+            .map(|sample| sample.weight.luminance())
+            .sum();
+
+        total / count as f32
+    }
+
+    // Same, integrating the evaluated BSDF over the sphere
+    fn evaluated_albedo(bsdf: &dyn Bsdf, wo: &Vec3, count: usize) -> f32 {
+        let mut rng = rng();
+        let total: f32 = (0..count)
+            .map(|_| {
+                let wi = uniform_sphere(&mut rng);
+                bsdf.evaluate(wo, &wi).luminance() * wi.z.abs() * 4.0 * PI
+            })
+            .sum();
+
+        total / count as f32
+    }
+
+    fn perfect_conductor(roughness: f32) -> Conductor {
+        // A null index of refraction reflects everything at any angle
+        Conductor::new(Color::black(), Color::new(1.0, 1.0, 1.0), roughness)
+    }
+
+    #[test]
+    fn ggx_normals_project_to_the_unit_disk() {
+        for &roughness in &[0.5, 0.8, 1.0] {
+            let distribution = GgxDistribution::new(roughness);
+            let mut rng = rng();
+
+            let count = 200_000;
+            let total: f32 = (0..count)
+                .map(|_| {
+                    let normal = uniform_sphere(&mut rng);
+                    distribution.d(&normal) * normal.z.max(0.0) * 4.0 * PI
+                })
+                .sum();
+
+            let projected_area = total / count as f32;
+            assert!(
+                (projected_area - 1.0).abs() < 0.03,
+                "roughness {}: {}",
+                roughness,
+                projected_area
+            );
+        }
+    }
+
+    #[test]
+    fn ggx_shadowing_is_a_fraction() {
+        let distribution = GgxDistribution::new(0.6);
+
+        assert!((distribution.g1(&Vec3::new(0.0, 0.0, 1.0)) - 1.0).abs() < 1e-6);
+
+        for &cos_o in &[0.1, 0.5, 0.9] {
+            for &cos_i in &[0.2, 0.7] {
+                let (wo, wi) = (direction(cos_o), direction(cos_i));
+                let g = distribution.g(&wo, &wi);
+
+                assert!(g > 0.0 && g <= distribution.g1(&wo) && g <= distribution.g1(&wi));
+            }
+        }
+    }
+
+    #[test]
+    fn visible_normals_face_the_view() {
+        let distribution = GgxDistribution::new(0.7);
+        let mut rng = rng();
+
+        for &cos_o in &[0.05, 0.5, 1.0] {
+            let wo = direction(cos_o);
+            for _ in 0..1000 {
+                let normal =
+                    distribution.sample_visible_normal(&wo, rng.next_f32(), rng.next_f32());
+
+                assert!((normal.length() - 1.0).abs() < 1e-4);
+                assert!(normal.z > 0.0);
+                assert!(Vec3::dot_product(&wo, &normal) >= -1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_weights_match_the_evaluated_bsdf() {
+        let bsdfs: Vec<Box<dyn Bsdf>> = vec![
+            Box::new(Conductor::gold(0.5)),
+            Box::new(Conductor::copper(0.8)),
+            Box::new(RoughDielectric::new(1.5, 0.6)),
+        ];
+
+        for bsdf in bsdfs.iter() {
+            for &cos_o in &[0.3, 0.9] {
+                let wo = direction(cos_o);
+                let sampled = sampled_albedo(bsdf.as_ref(), &wo, 100_000);
+                let evaluated = evaluated_albedo(bsdf.as_ref(), &wo, 200_000);
+
+                assert!(
+                    (sampled - evaluated).abs() < 0.05,
+                    "cos {}: sampled {} evaluated {}",
+                    cos_o,
+                    sampled,
+                    evaluated
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compensated_white_conductor_keeps_all_the_light() {
+        for &roughness in &[0.3, 0.6, 1.0] {
+            let conductor = perfect_conductor(roughness);
+
+            for &cos_o in &[0.2, 0.5, 0.95] {
+                let albedo = sampled_albedo(&conductor, &direction(cos_o), 50_000);
+                assert!(
+                    (albedo - 1.0).abs() < 0.03,
+                    "roughness {} cos {}: {}",
+                    roughness,
+                    cos_o,
+                    albedo
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn albedo_table_matches_the_single_scattering_integral() {
+        for &roughness in &[0.5, 1.0] {
+            let distribution = GgxDistribution::new(roughness);
+            let conductor = perfect_conductor(roughness);
+
+            for &cos_o in &[0.1, 0.5, 0.9] {
+                let wo = direction(cos_o);
+                let mut rng = rng();
+
+                let count = 200_000;
+                let total: f32 = (0..count)
+                    .map(|_| uniform_sphere(&mut rng))
+                    .filter(|wi| wi.z > 0.0)
+                    .map(|wi| {
+                        let mut half = wo + wi;
+                        half.normalize();
+
+                        distribution.d(&half) * distribution.g(&wo, &wi) / (4.0 * wo.z) * 4.0 * PI
+                    })
+                    .sum();
+
+                let albedo = conductor.albedo.get_albedo(cos_o);
+                assert!(albedo < 1.0);
+                assert!(
+                    (albedo - total / count as f32).abs() < 0.02,
+                    "roughness {} cos {}: {} {}",
+                    roughness,
+                    cos_o,
+                    albedo,
+                    total / count as f32
+                );
+            }
+        }
+
+        // Without shadowing nothing is lost
+        let table = AlbedoTable::new(&GgxDistribution::new(1.0), |wo, normal| 1.0);
+        assert!((table.get_albedo(0.5) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn compensated_rough_glass_keeps_all_the_light() {
+        let glass = RoughDielectric::new(1.5, 0.7);
+
+        for &cos_o in &[0.3, 0.9, -0.5] {
+            let albedo = sampled_albedo(&glass, &direction(cos_o), 50_000);
+            assert!((albedo - 1.0).abs() < 0.03, "cos {}: {}", cos_o, albedo);
+        }
+    }
+
+    #[test]
+    fn conductor_presets_reflect_their_color() {
+        // Reflectance at normal incidence, ((n - 1)^2 + k^2) / ((n + 1)^2 + k^2)
+        let expected =
+            |eta: f32, k: f32| ((eta - 1.0).powi(2) + k * k) / ((eta + 1.0).powi(2) + k * k);
+
+        let gold = Conductor::gold(0.5).get_reflectance();
+        assert!((gold.r - expected(0.143, 3.983)).abs() < 1e-4);
+        assert!((gold.g - expected(0.374, 2.385)).abs() < 1e-4);
+        assert!((gold.b - expected(1.442, 1.603)).abs() < 1e-4);
+        assert!(gold.r > gold.g && gold.g > gold.b);
+
+        let copper = Conductor::copper(0.5).get_reflectance();
+        assert!(copper.r > copper.g && copper.g > copper.b);
+
+        for metal in [Conductor::silver(0.5), Conductor::aluminium(0.5)].iter() {
+            let reflectance = metal.get_reflectance();
+            assert!(reflectance.r > 0.85 && reflectance.g > 0.85 && reflectance.b > 0.85);
+        }
+
+        // Everything is reflected at grazing angles
+        let grazing = fresnel_conductor(
+            0.0,
+            &Color::new(0.143, 0.374, 1.442),
+            &Color::new(3.983, 2.385, 1.603),
+        );
+        assert!(grazing.r > 0.999 && grazing.g > 0.999 && grazing.b > 0.999);
+    }
+
+    #[test]
+    fn dielectric_fresnel() {
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-4);
+        assert!((fresnel_dielectric(0.0, 1.5) - 1.0).abs() < 1e-4);
+
+        // Total internal reflection past the critical angle going out of glass
+        assert_eq!(fresnel_dielectric(0.5, 1.0 / 1.5), 1.0);
+        assert!(fresnel_dielectric(0.9, 1.0 / 1.5) < 0.1);
+
+        let schlick = fresnel_schlick(1.0, &Color::new(0.04, 0.5, 1.0));
+        assert!((schlick.r - 0.04).abs() < 1e-6 && (schlick.g - 0.5).abs() < 1e-6);
+        assert!((fresnel_schlick(0.0, &Color::new(0.04, 0.04, 0.04)).r - 1.0).abs() < 1e-6);
+    }
+}
@@ -1,12 +1,54 @@
+pub mod animation;
+pub mod aperture;
+pub mod bounds;
+pub mod bsdf;
 pub mod camera;
+pub mod checkpoint;
 pub mod color;
+pub mod cone;
+pub mod csg;
+pub mod cuboid;
+pub mod cylinder;
+pub mod diffuse;
 pub mod directional_light;
+pub mod disk;
+pub mod equirectangular_camera;
+pub mod film;
+pub mod fisheye_camera;
 pub mod geometry;
+pub mod heightfield;
+pub mod image;
+pub mod instance;
 pub mod light;
+pub mod microfacet;
+pub mod motion;
+pub mod mtl;
+pub mod noise;
+pub mod noise_texture;
+pub mod normal_map;
+pub mod normal_mapped;
+pub mod orthographic_camera;
+pub mod perspective_camera;
+pub mod physical_camera;
+pub mod plane;
 pub mod point_light;
+pub mod principled;
 pub mod ray;
+pub mod renderer;
 pub mod scene;
+pub mod scene_graph;
+pub mod sdf;
+pub mod solver;
 pub mod sphere;
 pub mod spot_light;
+pub mod stereo_camera;
+pub mod surface;
+pub mod texture;
+pub mod texture_cache;
+pub mod textured;
+pub mod textured_plane;
 pub mod textured_sphere;
+pub mod tile;
+pub mod torus;
+pub mod transform;
 pub mod vec3;
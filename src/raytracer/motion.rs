@@ -0,0 +1,76 @@
+use super::vec3::Vec3;
+
+// Offset of an object from its rest position over time
+#[derive(Clone, Debug, PartialEq)]
+pub enum Motion {
+    Static,
+    // Moves by `offset` between the start and end times, staying still outside of them
+    Linear {
+        start_time: f32,
+        end_time: f32,
+        offset: Vec3,
+    },
+    // Offsets at given times (sorted by time), linearly interpolated in between
+    Keyframed(Vec<(f32, Vec3)>),
+}
+
+impl Motion {
+    pub fn linear(start_time: f32, end_time: f32, offset: Vec3) -> Motion {
+        Motion::Linear {
+            start_time,
+            end_time,
+            offset,
+        }
+    }
+
+    pub fn keyframed(mut keyframes: Vec<(f32, Vec3)>) -> Motion {
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Motion::Keyframed(keyframes)
+    }
+
+    // Offsets between which the motion goes in straight lines
+    pub fn get_key_offsets(&self) -> Vec<Vec3> {
+        match self {
+            Motion::Static => vec![Vec3::zero()],
+            Motion::Linear { offset, .. } => vec![Vec3::zero(), *offset],
+            Motion::Keyframed(keyframes) if keyframes.is_empty() => vec![Vec3::zero()],
+            Motion::Keyframed(keyframes) => keyframes.iter().map(|(_, offset)| *offset).collect(),
+        }
+    }
+
+    pub fn get_offset(&self, time: f32) -> Vec3 {
+        match self {
+            Motion::Static => Vec3::zero(),
+            Motion::Linear {
+                start_time,
+                end_time,
+                offset,
+            } => {
+                if end_time > start_time {
+                    let factor = ((time - start_time) / (end_time - start_time)).clamp(0.0, 1.0);
+                    factor * offset
+                } else if time >= *start_time {
+                    *offset
+                } else {
+                    Vec3::zero()
+                }
+            }
+            Motion::Keyframed(keyframes) => {
+                let next = keyframes.partition_point(|(key_time, _)| *key_time <= time);
+                if next == 0 {
+                    keyframes
+                        .first()
+                        .map_or(Vec3::zero(), |(_, offset)| *offset)
+                } else if next == keyframes.len() {
+                    keyframes[next - 1].1
+                } else {
+                    let (previous_time, previous_offset) = keyframes[next - 1];
+                    let (next_time, next_offset) = keyframes[next];
+                    let factor = (time - previous_time) / (next_time - previous_time);
+
+                    previous_offset + factor * (next_offset - previous_offset)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use super::color::Color;
+use super::principled::MtlMaterial;
+use super::texture::{ColorSpace, Texture};
+use super::texture_cache::TextureCache;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid material library line: {}", line),
+    )
+}
+
+// Materials of an OBJ material library, by name.
+// Maps are loaded through the cache, relative to the library, options before the file name being ignored.
+// Statements which aren't supported are skipped.
+pub fn load_mtl(path: &Path, cache: &TextureCache) -> io::Result<HashMap<String, MtlMaterial>> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for line in fs::read_to_string(path)?.lines() {
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+
+            current = Some((arguments.join(" "), MtlMaterial::default()));
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None => continue,
+        };
+
+        let number = |index: usize| -> io::Result<f32> {
+            arguments
+                .get(index)
+                .and_then(|argument| argument.parse().ok())
+                .ok_or_else(|| invalid_line(line))
+        };
+
+        // A single value stands for grey
+        let color = || -> io::Result<Color> {
+            let r = number(0)?;
+            if arguments.len() < 3 {
+                return Ok(Color::new(r, r, r));
+            }
+
+            Ok(Color::new(r, number(1)?, number(2)?))
+        };
+
+        let texture = |color_space: ColorSpace| -> io::Result<Option<Arc<dyn Texture>>> {
+            let file = arguments.last().ok_or_else(|| invalid_line(line))?;
+            let texture = cache.load_png(&directory.join(file), color_space)?;
+
+            Ok(Some(Arc::new(texture)))
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = color()?,
+            "map_Kd" => material.diffuse_texture = texture(ColorSpace::Srgb)?,
+            "Ks" => material.specular = color()?,
+            "map_Ks" => material.specular_texture = texture(ColorSpace::Srgb)?,
+            "Ns" => material.shininess = number(0)?,
+            "Ni" => material.ior = number(0)?,
+            "d" => material.dissolve = number(0)?,
+            "map_d" => material.dissolve_texture = texture(ColorSpace::Linear)?,
+            "Tr" => material.dissolve = 1.0 - number(0)?,
+            "Tf" => material.transmission_filter = color()?,
+            "illum" => {
+                material.illum = arguments
+                    .first()
+                    .and_then(|argument| argument.parse().ok())
+                    .ok_or_else(|| invalid_line(line))?
+            }
+            "Pr" => material.roughness = Some(number(0)?),
+            "map_Pr" => material.roughness_texture = texture(ColorSpace::Linear)?,
+            "Pm" => material.metallic = number(0)?,
+            "map_Pm" => material.metallic_texture = texture(ColorSpace::Linear)?,
+            "Ps" => material.sheen = number(0)?,
+            "map_Ps" => material.sheen_texture = texture(ColorSpace::Linear)?,
+            "Pc" => material.clearcoat = number(0)?,
+            "Pcr" => material.clearcoat_roughness = number(0)?,
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material);
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn opacity_and_transmission_statements() {
+        let path = env::temp_dir().join(format!("raytracer-{}.mtl", std::process::id()));
+        fs::write(
+            &path,
+            "newmtl leaf\nd 0.5\n\nnewmtl veil\nTr 0.25\n\nnewmtl glass\nTf 0.9 1 0.8\nillum 7\nNs 250\n",
+        )
+        .unwrap();
+
+        let materials = load_mtl(&path, &TextureCache::new());
+        fs::remove_file(&path).unwrap();
+        let materials = materials.unwrap();
+
+        assert_eq!(materials["leaf"].dissolve, 0.5);
+        assert_eq!(materials["leaf"].illum, 2);
+        assert_eq!(materials["veil"].dissolve, 0.75);
+
+        let glass = &materials["glass"];
+        assert_eq!(glass.dissolve, 1.0);
+        assert_eq!(glass.illum, 7);
+        assert_eq!(glass.transmission_filter, Color::new(0.9, 1.0, 0.8));
+        assert_eq!(glass.shininess, 250.0);
+    }
+}
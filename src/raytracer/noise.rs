@@ -0,0 +1,310 @@
+use super::vec3::Vec3;
+use rand::{Rng, SeedableRng};
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Dot product of the offset with one of the 12 edge directions of a cube, picked by the hash
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+// Octaves summed by fractal noises, each one having `lacunarity` times the frequency
+// and `gain` times the amplitude of the previous one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fractal {
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Fractal {
+    pub fn new(octaves: u32) -> Fractal {
+        Fractal {
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn with_lacunarity(self, lacunarity: f32) -> Fractal {
+        Fractal { lacunarity, ..self }
+    }
+
+    pub fn with_gain(self, gain: f32) -> Fractal {
+        Fractal { gain, ..self }
+    }
+}
+
+// Noise functions over space, a given seed always giving the same values
+#[derive(Clone, Debug, PartialEq)]
+pub struct Noise {
+    // Shuffled 0 to 255, twice so lookups don't have to wrap
+    permutation: Vec<u8>,
+    seed: u32,
+}
+
+impl Noise {
+    pub fn new(seed: u32) -> Noise {
+        let mut rng = rand::XorShiftRng::from_seed([seed, 0x9E37_79B9, 0x7F4A_7C15, 0xBF58_476D]);
+
+        let mut permutation: Vec<u8> = (0..=255).collect();
+        rng.shuffle(&mut permutation);
+        permutation.extend_from_within(..);
+
+        Noise { permutation, seed }
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.seed
+    }
+
+    fn hash(&self, x: i64, y: i64, z: i64) -> u8 {
+        let p = &self.permutation;
+        p[p[p[(x & 255) as usize] as usize + (y & 255) as usize] as usize + (z & 255) as usize]
+    }
+
+    // Improved Perlin noise, from about -1 to 1 and zero on integer coordinates
+    pub fn perlin(&self, point: &Vec3) -> f32 {
+        let (cell_x, cell_y, cell_z) = (point.x.floor(), point.y.floor(), point.z.floor());
+        let (x, y, z) = (point.x - cell_x, point.y - cell_y, point.z - cell_z);
+        let (cell_x, cell_y, cell_z) = (cell_x as i64, cell_y as i64, cell_z as i64);
+
+        let corner = |dx: i64, dy: i64, dz: i64| {
+            gradient(
+                self.hash(cell_x + dx, cell_y + dy, cell_z + dz),
+                x - dx as f32,
+                y - dy as f32,
+                z - dz as f32,
+            )
+        };
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        lerp(
+            lerp(
+                lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+                lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+                v,
+            ),
+            lerp(
+                lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+                lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+                v,
+            ),
+            w,
+        )
+    }
+
+    // Simplex noise, from about -1 to 1, with fewer directional artifacts than Perlin noise
+    pub fn simplex(&self, point: &Vec3) -> f32 {
+        const SKEW: f32 = 1.0 / 3.0;
+        const UNSKEW: f32 = 1.0 / 6.0;
+
+        // Cell of the skewed grid, made of six tetrahedra
+        let skew = (point.x + point.y + point.z) * SKEW;
+        let cell = [
+            (point.x + skew).floor(),
+            (point.y + skew).floor(),
+            (point.z + skew).floor(),
+        ];
+        let unskew = (cell[0] + cell[1] + cell[2]) * UNSKEW;
+        let offset = [
+            point.x - (cell[0] - unskew),
+            point.y - (cell[1] - unskew),
+            point.z - (cell[2] - unskew),
+        ];
+
+        // Tetrahedron containing the point, from the order of its coordinates
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| offset[b].total_cmp(&offset[a]));
+        let mut first = [0i64; 3];
+        first[order[0]] = 1;
+        let mut second = first;
+        second[order[1]] = 1;
+
+        let corners = [[0i64; 3], first, second, [1; 3]];
+
+        corners
+            .iter()
+            .enumerate()
+            .map(|(i, corner)| {
+                let x = offset[0] - corner[0] as f32 + i as f32 * UNSKEW;
+                let y = offset[1] - corner[1] as f32 + i as f32 * UNSKEW;
+                let z = offset[2] - corner[2] as f32 + i as f32 * UNSKEW;
+
+                let falloff = 0.6 - x * x - y * y - z * z;
+                if falloff <= 0.0 {
+                    return 0.0;
+                }
+
+                let hash = self.hash(
+                    cell[0] as i64 + corner[0],
+                    cell[1] as i64 + corner[1],
+                    cell[2] as i64 + corner[2],
+                );
+
+                falloff.powi(4) * gradient(hash % 12, x, y, z)
+            })
+            .sum::<f32>()
+            * 32.0
+    }
+
+    // Fractal Brownian motion, Perlin noise summed over octaves, from about -1 to 1
+    pub fn fbm(&self, point: &Vec3, fractal: &Fractal) -> f32 {
+        self.sum_octaves(point, fractal, |value| value)
+    }
+
+    // As fbm, but with the absolute value of each octave, giving creases, from 0 to about 1
+    pub fn turbulence(&self, point: &Vec3, fractal: &Fractal) -> f32 {
+        self.sum_octaves(point, fractal, f32::abs)
+    }
+
+    // Weighted mean of the octaves, so the range doesn't depend on their count
+    fn sum_octaves(&self, point: &Vec3, fractal: &Fractal, octave: impl Fn(f32) -> f32) -> f32 {
+        let mut total = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for _ in 0..fractal.octaves {
+            total += amplitude * octave(self.perlin(&(*point * frequency)));
+            total_amplitude += amplitude;
+            amplitude *= fractal.gain;
+            frequency *= fractal.lacunarity;
+        }
+
+        if total_amplitude > 0.0 {
+            total / total_amplitude
+        } else {
+            0.0
+        }
+    }
+
+    // Cellular noise, distances to the closest and second closest of feature points scattered one per unit cell
+    pub fn worley(&self, point: &Vec3) -> (f32, f32) {
+        let cell = [
+            point.x.floor() as i64,
+            point.y.floor() as i64,
+            point.z.floor() as i64,
+        ];
+
+        let mut closest = f32::INFINITY;
+        let mut second_closest = f32::INFINITY;
+
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (x, y, z) = (cell[0] + dx, cell[1] + dy, cell[2] + dz);
+
+                    // Position of the point inside its cell, from successive permutation values
+                    let hash = self.hash(x, y, z) as usize;
+                    let jitter = |i: usize| self.permutation[hash + i] as f32 / 256.0;
+
+                    let feature = Vec3::new(
+                        x as f32 + jitter(0),
+                        y as f32 + jitter(1),
+                        z as f32 + jitter(2),
+                    );
+
+                    let distance = (feature - point).length();
+                    if distance < closest {
+                        second_closest = closest;
+                        closest = distance;
+                    } else if distance < second_closest {
+                        second_closest = distance;
+                    }
+                }
+            }
+        }
+
+        (closest, second_closest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points() -> Vec<Vec3> {
+        let mut rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+
+        (0..500)
+            .map(|_| {
+                Vec3::new(
+                    rng.next_f32() * 20.0 - 10.0,
+                    rng.next_f32() * 20.0 - 10.0,
+                    rng.next_f32() * 20.0 - 10.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_noise() {
+        let (noise, same) = (Noise::new(42), Noise::new(42));
+        let other = Noise::new(43);
+        let fractal = Fractal::new(4);
+        let mut differences = 0;
+
+        for point in points().iter() {
+            assert_eq!(noise.perlin(point), same.perlin(point));
+            assert_eq!(noise.simplex(point), same.simplex(point));
+            assert_eq!(noise.fbm(point, &fractal), same.fbm(point, &fractal));
+            assert_eq!(noise.worley(point), same.worley(point));
+
+            if noise.perlin(point) != other.perlin(point) {
+                differences += 1;
+            }
+        }
+
+        assert_eq!(noise, same);
+        assert_ne!(noise, other);
+        assert!(differences > 450, "{}", differences);
+    }
+
+    #[test]
+    fn perlin_is_zero_on_the_lattice() {
+        let noise = Noise::new(7);
+
+        for x in -3..4 {
+            for y in -3..4 {
+                for z in -3..4 {
+                    let point = Vec3::new(x as f32, y as f32, z as f32);
+                    assert_eq!(noise.perlin(&point), 0.0);
+                }
+            }
+        }
+
+        // But not in between
+        assert!(points().iter().any(|point| noise.perlin(point).abs() > 0.1));
+    }
+
+    #[test]
+    fn worley_distances_are_ordered() {
+        let noise = Noise::new(7);
+
+        for point in points().iter() {
+            let (closest, second_closest) = noise.worley(point);
+
+            assert!(closest >= 0.0);
+            assert!(closest <= second_closest);
+            assert!(second_closest.is_finite());
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use super::color::Color;
+use super::geometry::HitInfo;
+use super::noise::{Fractal, Noise};
+use super::texture::Texture;
+use super::vec3::Vec3;
+use std::f32::consts::PI;
+
+fn mix(first: &Color, second: &Color, factor: f32) -> Color {
+    let factor = factor.clamp(0.0, 1.0);
+
+    Color {
+        r: first.r + (second.r - first.r) * factor,
+        g: first.g + (second.g - first.g) * factor,
+        b: first.b + (second.b - first.b) * factor,
+    }
+}
+
+// Patterns below are solid, computed from the position of the hit (in object space for instances)
+// multiplied by `scale`, so they don't depend on how surfaces are parameterized
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoiseKind {
+    Perlin,
+    Simplex,
+    Fbm(Fractal),
+    Turbulence(Fractal),
+    // Distance to the closest feature point
+    Worley,
+}
+
+// Raw noise, going from the first color (lowest values) to the second one
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseTexture {
+    first: Color,
+    kind: NoiseKind,
+    noise: Noise,
+    scale: f32,
+    second: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(
+        kind: NoiseKind,
+        seed: u32,
+        scale: f32,
+        first: Color,
+        second: Color,
+    ) -> NoiseTexture {
+        NoiseTexture {
+            first,
+            kind,
+            noise: Noise::new(seed),
+            scale,
+            second,
+        }
+    }
+
+    // Noise value brought to the 0 to 1 range
+    pub fn get_value(&self, point: &Vec3) -> f32 {
+        let point = *point * self.scale;
+
+        match &self.kind {
+            NoiseKind::Perlin => self.noise.perlin(&point) * 0.5 + 0.5,
+            NoiseKind::Simplex => self.noise.simplex(&point) * 0.5 + 0.5,
+            NoiseKind::Fbm(fractal) => self.noise.fbm(&point, fractal) * 0.5 + 0.5,
+            NoiseKind::Turbulence(fractal) => self.noise.turbulence(&point, fractal),
+            NoiseKind::Worley => self.noise.worley(&point).0,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        mix(
+            &self.first,
+            &self.second,
+            self.get_value(&hit_info.position),
+        )
+    }
+}
+
+// Veins along x, bent by turbulence
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarbleTexture {
+    base: Color,
+    fractal: Fractal,
+    noise: Noise,
+    scale: f32,
+    // How much the veins are bent
+    turbulence: f32,
+    vein: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u32, scale: f32, base: Color, vein: Color) -> MarbleTexture {
+        MarbleTexture {
+            base,
+            fractal: Fractal::new(5),
+            noise: Noise::new(seed),
+            scale,
+            turbulence: 5.0,
+            vein,
+        }
+    }
+
+    pub fn with_fractal(self, fractal: Fractal) -> MarbleTexture {
+        MarbleTexture { fractal, ..self }
+    }
+
+    pub fn with_turbulence(self, turbulence: f32) -> MarbleTexture {
+        MarbleTexture { turbulence, ..self }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        let point = hit_info.position * self.scale;
+        let phase = point.x + self.turbulence * self.noise.turbulence(&point, &self.fractal);
+
+        // Thin veins, where the sine is close to zero
+        let vein = 1.0 - (phase * PI).sin().abs();
+
+        mix(&self.base, &self.vein, vein.powi(6))
+    }
+}
+
+// Growth rings around the y axis, made irregular by noise
+#[derive(Clone, Debug, PartialEq)]
+pub struct WoodTexture {
+    dark: Color,
+    // How much the rings are distorted
+    distortion: f32,
+    fractal: Fractal,
+    light: Color,
+    noise: Noise,
+    // Rings per unit of distance to the axis
+    ring_frequency: f32,
+    scale: f32,
+}
+
+impl WoodTexture {
+    pub fn new(seed: u32, scale: f32, light: Color, dark: Color) -> WoodTexture {
+        WoodTexture {
+            dark,
+            distortion: 0.8,
+            fractal: Fractal::new(3),
+            light,
+            noise: Noise::new(seed),
+            ring_frequency: 8.0,
+            scale,
+        }
+    }
+
+    pub fn with_rings(self, ring_frequency: f32, distortion: f32) -> WoodTexture {
+        WoodTexture {
+            ring_frequency,
+            distortion,
+            ..self
+        }
+    }
+
+    pub fn with_fractal(self, fractal: Fractal) -> WoodTexture {
+        WoodTexture { fractal, ..self }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        let point = hit_info.position * self.scale;
+
+        // Stretched along the axis, as the grain of the wood
+        let grain = Vec3::new(point.x, point.y * 0.2, point.z);
+        let radius = (point.x * point.x + point.z * point.z).sqrt();
+        let ring = radius * self.ring_frequency
+            + self.distortion * self.noise.fbm(&grain, &self.fractal) * self.ring_frequency;
+
+        // Rings get dark quickly and light again slowly
+        let fraction = ring - ring.floor();
+        mix(&self.light, &self.dark, fraction.powi(3))
+    }
+}
+
+// Crystals of three colors, speckled by cellular noise
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraniteTexture {
+    base: Color,
+    fractal: Fractal,
+    noise: Noise,
+    scale: f32,
+    speckle: Color,
+    vein: Color,
+}
+
+impl GraniteTexture {
+    pub fn new(seed: u32, scale: f32, base: Color, speckle: Color, vein: Color) -> GraniteTexture {
+        GraniteTexture {
+            base,
+            fractal: Fractal::new(4),
+            noise: Noise::new(seed),
+            scale,
+            speckle,
+            vein,
+        }
+    }
+
+    pub fn with_fractal(self, fractal: Fractal) -> GraniteTexture {
+        GraniteTexture { fractal, ..self }
+    }
+}
+
+impl Texture for GraniteTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        let point = hit_info.position * self.scale;
+
+        // Borders between crystals are where the two closest feature points are about as close
+        let (closest, second_closest) = self.noise.worley(&point);
+        let border = 1.0 - ((second_closest - closest) * 4.0).min(1.0);
+
+        let grain = self.noise.fbm(&(point * 4.0), &self.fractal) * 0.5 + 0.5;
+        let speckled = mix(&self.base, &self.speckle, (grain - 0.45) * 5.0);
+
+        mix(&speckled, &self.vein, border.powi(4))
+    }
+}
@@ -0,0 +1,206 @@
+use super::geometry::HitInfo;
+use super::texture::Texture;
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+// Detail added to the shading normal, without changing the shape of the surface
+#[derive(Clone)]
+pub enum NormalMap {
+    // Heights (from the average of the channels) the surface seems displaced by along its normal,
+    // multiplied by `scale`
+    Bump {
+        height: Arc<dyn Texture>,
+        scale: f32,
+    },
+    // Normals in the tangent frame of the surface, stored as colors (with x along u and y along v).
+    // Textures read from images have to be kept linear.
+    TangentSpace {
+        texture: Arc<dyn Texture>,
+        strength: f32,
+    },
+}
+
+impl NormalMap {
+    pub fn bump(height: Arc<dyn Texture>, scale: f32) -> NormalMap {
+        NormalMap::Bump { height, scale }
+    }
+
+    pub fn tangent_space(texture: Arc<dyn Texture>) -> NormalMap {
+        NormalMap::TangentSpace {
+            texture,
+            strength: 1.0,
+        }
+    }
+
+    // Scales how much the map bends normals
+    pub fn with_strength(self, strength: f32) -> NormalMap {
+        match self {
+            NormalMap::Bump { height, .. } => NormalMap::Bump {
+                height,
+                scale: strength,
+            },
+            NormalMap::TangentSpace { texture, .. } => {
+                NormalMap::TangentSpace { texture, strength }
+            }
+        }
+    }
+
+    // Unit normal of the surface once perturbed
+    pub fn perturb(&self, hit_info: &HitInfo) -> Vec3 {
+        let (tangent, bitangent, normal) = hit_info.get_tangent_frame();
+
+        let mut perturbed = match self {
+            NormalMap::Bump { height, scale } => {
+                match NormalMap::bump_normal(hit_info, height.as_ref(), *scale, &normal) {
+                    Some(perturbed) => perturbed,
+                    None => return normal,
+                }
+            }
+            NormalMap::TangentSpace { texture, strength } => {
+                let color = texture.evaluate(hit_info);
+
+                // Normals are kept on the outer side of the surface
+                tangent * ((color.r * 2.0 - 1.0) * strength)
+                    + bitangent * ((color.g * 2.0 - 1.0) * strength)
+                    + normal * (color.b * 2.0 - 1.0).max(1e-3)
+            }
+        };
+
+        perturbed.normalize();
+        perturbed
+    }
+
+    // Normal of the displaced surface, from the slopes of the heights along u and v
+    fn bump_normal(
+        hit_info: &HitInfo,
+        height: &dyn Texture,
+        scale: f32,
+        normal: &Vec3,
+    ) -> Option<Vec3> {
+        // Heights are compared over about half the footprint of the hit, or a small fixed step
+        let step = |derivative: &Vec3| {
+            let length = derivative.length();
+            if hit_info.footprint > 0.0 && length > 0.0 {
+                0.5 * hit_info.footprint / length
+            } else {
+                5e-4
+            }
+        };
+        let (step_u, step_v) = (step(&hit_info.dpdu), step(&hit_info.dpdv));
+        let (u, v) = hit_info.uv;
+
+        let base = height.evaluate_scalar(hit_info);
+        let along_u = height.evaluate_scalar(&HitInfo {
+            position: hit_info.position + hit_info.dpdu * step_u,
+            uv: (u + step_u, v),
+            ..*hit_info
+        });
+        let along_v = height.evaluate_scalar(&HitInfo {
+            position: hit_info.position + hit_info.dpdv * step_v,
+            uv: (u, v + step_v),
+            ..*hit_info
+        });
+
+        let displaced_dpdu = hit_info.dpdu + *normal * ((along_u - base) / step_u * scale);
+        let displaced_dpdv = hit_info.dpdv + *normal * ((along_v - base) / step_v * scale);
+
+        let mut perturbed = Vec3::cross_product(&displaced_dpdu, &displaced_dpdv);
+        if perturbed.squared_length() < 1e-12 {
+            return None;
+        }
+
+        // The cross product may point inward, depending on the parameterization
+        if Vec3::dot_product(&perturbed, normal) < 0.0 {
+            perturbed = -perturbed;
+        }
+
+        Some(perturbed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::color::Color;
+    use crate::raytracer::texture::{ConstantTexture, ProceduralTexture};
+
+    // Hit on a floor, u going along x and v against z
+    fn floor_hit() -> HitInfo {
+        HitInfo {
+            position: Vec3::new(0.3, 0.0, -0.2),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            geometric_normal: Vec3::new(0.0, 1.0, 0.0),
+            uv: (0.3, 0.2),
+            dpdu: Vec3::new(1.0, 0.0, 0.0),
+            dpdv: Vec3::new(0.0, 0.0, -1.0),
+            footprint: 0.0,
+        }
+    }
+
+    #[test]
+    fn tangent_frame_follows_the_derivatives() {
+        let (tangent, bitangent, normal) = floor_hit().get_tangent_frame();
+        assert!((tangent - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-6);
+        assert!((bitangent - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6);
+        assert!((normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6);
+
+        // Derivatives along the normal give an arbitrary frame, still orthonormal
+        let mut hit_info = floor_hit();
+        hit_info.dpdu = Vec3::new(0.0, 2.0, 0.0);
+        let (tangent, bitangent, normal) = hit_info.get_tangent_frame();
+        assert!(Vec3::dot_product(&tangent, &normal).abs() < 1e-6);
+        assert!(Vec3::dot_product(&bitangent, &normal).abs() < 1e-6);
+        assert!(Vec3::dot_product(&tangent, &bitangent).abs() < 1e-6);
+    }
+
+    #[test]
+    fn flat_tangent_space_map_keeps_the_normal() {
+        let map =
+            NormalMap::tangent_space(Arc::new(ConstantTexture::new(Color::new(0.5, 0.5, 1.0))));
+
+        let normal = map.perturb(&floor_hit());
+        assert!((normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn bumps_tilt_the_normal_against_the_slope() {
+        // Heights going up by 0.5 per unit along u
+        let ramp = ProceduralTexture::new(|hit_info: &HitInfo| {
+            let height = 0.5 * hit_info.uv.0;
+            Color::new(height, height, height)
+        });
+        let map = NormalMap::bump(Arc::new(ramp), 1.0);
+
+        let mut expected = Vec3::new(-0.5, 1.0, 0.0);
+        expected.normalize();
+
+        let normal = map.perturb(&floor_hit());
+        assert!((normal - expected).length() < 1e-3, "{:?}", normal);
+
+        // With a footprint, the heights are compared further away, on the same slope
+        let mut hit_info = floor_hit();
+        hit_info.footprint = 0.1;
+        let normal = map.perturb(&hit_info);
+        assert!((normal - expected).length() < 1e-3, "{:?}", normal);
+    }
+
+    #[test]
+    fn lights_behind_the_surface_are_ignored() {
+        // Normal bent almost along the tangent
+        let map =
+            NormalMap::tangent_space(Arc::new(ConstantTexture::new(Color::new(1.0, 0.5, 0.5))));
+        let mut hit_info = floor_hit();
+        hit_info.normal = map.perturb(&hit_info);
+        assert!(hit_info.normal.x > 0.99);
+
+        // Below the surface, while the perturbed normal faces it
+        let mut below = Vec3::new(1.0, -0.3, 0.0);
+        below.normalize();
+        assert!(Vec3::dot_product(&below, &hit_info.normal) > 0.0);
+        assert_eq!(hit_info.get_diffuse_factor(&below), 0.0);
+
+        let mut above = Vec3::new(1.0, 0.3, 0.0);
+        above.normalize();
+        assert!(hit_info.get_diffuse_factor(&above) > 0.9);
+    }
+}
@@ -0,0 +1,77 @@
+use super::bounds::BoundingBox;
+use super::bsdf::Bsdf;
+use super::color::Color;
+use super::geometry::{Geometry, HitInfo, Interval};
+use super::normal_map::NormalMap;
+use super::ray::Ray;
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+// Gives any geometry the detail of a bump or normal map
+#[derive(Clone)]
+pub struct NormalMapped {
+    geometry: Arc<dyn Geometry>,
+    normal_map: NormalMap,
+}
+
+impl NormalMapped {
+    pub fn new(geometry: Arc<dyn Geometry>, normal_map: NormalMap) -> NormalMapped {
+        NormalMapped {
+            geometry,
+            normal_map,
+        }
+    }
+
+    pub fn get_geometry(&self) -> &Arc<dyn Geometry> {
+        &self.geometry
+    }
+
+    pub fn get_normal_map(&self) -> &NormalMap {
+        &self.normal_map
+    }
+}
+
+impl Geometry for NormalMapped {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        self.geometry.compute_hit(ray, hitinfo, exit_dist)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.geometry.get_color(hit_info)
+    }
+
+    // Maps of the wrapped geometry come first, so maps can be stacked
+    fn get_shading_normal(&self, hit_info: &HitInfo) -> Vec3 {
+        let normal = self.geometry.get_shading_normal(hit_info);
+
+        self.normal_map.perturb(&HitInfo {
+            normal,
+            ..*hit_info
+        })
+    }
+
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Option<Box<dyn Bsdf>> {
+        self.geometry.get_bsdf(hit_info)
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.geometry.get_reflection_factor(hit_info)
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.geometry.get_transparency_factor(hit_info)
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.geometry.get_bounds()
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        self.geometry.get_intervals(ray)
+    }
+}
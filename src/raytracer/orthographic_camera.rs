@@ -0,0 +1,51 @@
+use super::camera::{camera_basis, Camera, Shutter};
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+// All rays are parallel to the view direction, objects keep their size whatever their distance
+pub struct OrthographicCamera {
+    direction: Vec3,
+    horizontal: Vec3,
+    left_corner: Vec3,
+    shutter: Shutter,
+    vertical: Vec3,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        aspect: f32,
+        view_width: f32,
+    ) -> OrthographicCamera {
+        let half_width = view_width / 2.0;
+        let half_height = half_width / aspect;
+
+        let (u, v, w) = camera_basis(&position, &lookat, &up);
+
+        OrthographicCamera {
+            direction: -w,
+            horizontal: 2.0 * half_width * u,
+            left_corner: position - half_width * u + half_height * v,
+            shutter: Shutter::default(),
+            vertical: -2.0 * half_height * v,
+        }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter) -> OrthographicCamera {
+        OrthographicCamera { shutter, ..self }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Option<Ray> {
+        Some(
+            Ray::new(
+                self.left_corner + self.horizontal * x + self.vertical * y,
+                self.direction,
+            )
+            .with_time(self.shutter.sample(rng)),
+        )
+    }
+}
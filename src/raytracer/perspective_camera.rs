@@ -0,0 +1,207 @@
+use super::aperture::Aperture;
+use super::camera::{camera_basis, Camera, Shutter};
+use super::physical_camera::PhysicalCameraSettings;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Clone, Debug)]
+pub struct PerspectiveCamera {
+    aperture: Aperture,
+    exposure: f32,
+    left_corner: Vec3,
+    lens_radius: f32,
+    lens_u: Vec3,
+    lens_v: Vec3,
+    horizontal: Vec3,
+    position: Vec3,
+    shutter: Shutter,
+    vertical: Vec3,
+}
+
+impl PerspectiveCamera {
+    pub fn new(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        aspect: f32,
+        fovy: f32,
+        aperture: f32,
+        focus_dist: f32,
+    ) -> PerspectiveCamera {
+        let lens_radius = aperture / 2.0;
+        let half_theta = fovy.to_radians() / 2.0;
+        let half_height = half_theta.tan();
+        let half_width = half_height * aspect;
+
+        let (u, v, w) = camera_basis(&position, &lookat, &up);
+
+        // The image starts from the top left corner, rows going down against v (which points up), so renders
+        // keep the orientation of the original camera, whose v pointed down because of a wrong cross product
+        let left_corner =
+            position - half_width * focus_dist * u + half_height * focus_dist * v - focus_dist * w;
+        let horizontal = 2.0 * half_width * focus_dist * u;
+        let vertical = -2.0 * half_height * focus_dist * v;
+
+        PerspectiveCamera {
+            aperture: Aperture::disk(),
+            exposure: 1.0,
+            left_corner,
+            lens_radius,
+            lens_u: u,
+            lens_v: v,
+            horizontal,
+            position,
+            shutter: Shutter::default(),
+            vertical,
+        }
+    }
+
+    // Field of view, lens radius and exposure are derived from the settings, the image being fit vertically on the sensor.
+    // The shutter opens at `shutter_open`, the start of the frame, and stays open for the shutter speed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_physical(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        aspect: f32,
+        settings: &PhysicalCameraSettings,
+        focus_dist: f32,
+        shutter_open: f32,
+    ) -> PerspectiveCamera {
+        PerspectiveCamera {
+            exposure: settings.get_exposure(),
+            shutter: Shutter::new(shutter_open, shutter_open + settings.shutter_speed),
+            ..PerspectiveCamera::new(
+                position,
+                lookat,
+                up,
+                aspect,
+                settings.get_fovy(),
+                settings.get_aperture(),
+                focus_dist,
+            )
+        }
+    }
+
+    // Moves the eye while keeping the image plane (at focus distance) where it was, as for off-axis stereo
+    pub fn with_eye_offset(&self, offset: Vec3) -> PerspectiveCamera {
+        PerspectiveCamera {
+            position: self.position + offset,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter) -> PerspectiveCamera {
+        PerspectiveCamera { shutter, ..self }
+    }
+
+    pub fn with_aperture(self, aperture: Aperture) -> PerspectiveCamera {
+        PerspectiveCamera { aperture, ..self }
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn get_exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Option<Ray> {
+        let origin = self.position;
+        let (lens_x, lens_y) = self.aperture.sample(rng);
+        let offset = self.lens_radius * (lens_x * self.lens_u + lens_y * self.lens_v);
+
+        Some(
+            Ray::new(
+                origin + offset,
+                self.left_corner + self.horizontal * x + self.vertical * y - origin - offset,
+            )
+            .with_time(self.shutter.sample(rng)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn direction(camera: &dyn Camera, x: f32, y: f32) -> Vec3 {
+        let mut rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut direction = *camera.get_ray(&mut rng, x, y).unwrap().get_direction();
+        direction.normalize();
+
+        direction
+    }
+
+    fn assert_close(left: &Vec3, right: &Vec3) {
+        assert!((left - right).length() < 1e-5, "{:?} != {:?}", left, right);
+    }
+
+    // Rays of the original camera, looking horizontally toward -z with a 90 degrees field of view
+    #[test]
+    fn image_keeps_the_original_orientation() {
+        let camera = PerspectiveCamera::new(
+            Vec3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 0.5, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            2.0,
+            90.0,
+            0.0,
+            2.0,
+        );
+
+        let top_left = Vec3::new(-2.0, 1.0, -1.0) * (1.0 / 6f32.sqrt());
+        let bottom_right = Vec3::new(2.0, -1.0, -1.0) * (1.0 / 6f32.sqrt());
+
+        assert_close(&direction(&camera, 0.0, 0.0), &top_left);
+        assert_close(&direction(&camera, 1.0, 1.0), &bottom_right);
+        assert_close(&direction(&camera, 0.5, 0.5), &Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn tilted_image_top_is_up() {
+        let camera = PerspectiveCamera::new(
+            Vec3::new(-1.0, 1.0, 0.5),
+            Vec3::new(0.0, 0.5, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            16.0 / 9.0,
+            90.0,
+            0.0,
+            2.0,
+        );
+
+        let top = direction(&camera, 0.5, 0.0);
+        let bottom = direction(&camera, 0.5, 1.0);
+        let left = direction(&camera, 0.0, 0.5);
+        let right = direction(&camera, 1.0, 0.5);
+
+        assert!(top.y > bottom.y);
+        // Horizontal edges stay level
+        assert!((left.y - right.y).abs() < 1e-5);
+        // Looking toward -z and +x, the left edge is further toward -z
+        assert!(left.z < right.z);
+    }
+
+    #[test]
+    fn physical_shutter_opens_at_the_frame_start() {
+        let settings = PhysicalCameraSettings::full_frame(35.0, 2.8, 3200.0, 1.0 / 30.0);
+        let camera = PerspectiveCamera::new_physical(
+            Vec3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 0.5, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            settings.get_aspect(),
+            &settings,
+            2.0,
+            2.5,
+        );
+
+        let mut rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+        for _ in 0..100 {
+            let time = camera.get_ray(&mut rng, 0.5, 0.5).unwrap().get_time();
+            assert!((2.5..=2.5 + 1.0 / 30.0).contains(&time));
+        }
+
+        // Saturating at about 9 cd/m²
+        assert!((1.0 / camera.get_exposure() - 8.82).abs() < 0.01);
+    }
+}
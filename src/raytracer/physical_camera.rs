@@ -0,0 +1,58 @@
+// Real camera settings, distances in the scene being expressed in meters and radiances in cd/m²
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PhysicalCameraSettings {
+    // In millimeters
+    pub focal_length: f32,
+    // In millimeters, 36x24 for a full frame sensor
+    pub sensor_width: f32,
+    pub sensor_height: f32,
+    pub f_stop: f32,
+    pub iso: f32,
+    // In seconds
+    pub shutter_speed: f32,
+}
+
+impl PhysicalCameraSettings {
+    pub fn full_frame(focal_length: f32, f_stop: f32, iso: f32, shutter_speed: f32) -> Self {
+        PhysicalCameraSettings {
+            focal_length,
+            sensor_width: 36.0,
+            sensor_height: 24.0,
+            f_stop,
+            iso,
+            shutter_speed,
+        }
+    }
+
+    pub fn get_aspect(&self) -> f32 {
+        self.sensor_width / self.sensor_height
+    }
+
+    // Vertical field of view in degrees
+    pub fn get_fovy(&self) -> f32 {
+        2.0 * (self.sensor_height / (2.0 * self.focal_length))
+            .atan()
+            .to_degrees()
+    }
+
+    // Diameter of the entrance pupil in meters
+    pub fn get_aperture(&self) -> f32 {
+        self.focal_length / 1000.0 / self.f_stop
+    }
+
+    pub fn get_ev100(&self) -> f32 {
+        (self.f_stop * self.f_stop / self.shutter_speed * 100.0 / self.iso).log2()
+    }
+
+    // Radiance saturating the sensor (saturation based ISO, with q = 0.65), about 9 cd/m² at f/2.8, 1/30s and
+    // ISO 3200 but thousands in daylight settings. Lights of scenes made for a film exposure of 1 (where 1 is white)
+    // have to be scaled by it to look the same.
+    pub fn get_saturation_radiance(&self) -> f32 {
+        1.2 * self.get_ev100().exp2()
+    }
+
+    // Scale from scene radiance to film value, the sensor saturating at 1
+    pub fn get_exposure(&self) -> f32 {
+        1.0 / self.get_saturation_radiance()
+    }
+}
@@ -0,0 +1,190 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::{Crossing, Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    color: Color,
+    // Half sizes along the u and v axes, infinite if None
+    extents: Option<(f32, f32)>,
+    normal: Vec3,
+    point: Vec3,
+    reflection_factor: Option<f32>,
+    transparency_factor: Option<f32>,
+    u_axis: Vec3,
+    v_axis: Vec3,
+}
+
+impl Plane {
+    pub fn new(
+        point: Vec3,
+        normal: Vec3,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Plane {
+        let mut normal = normal;
+        normal.normalize();
+
+        let (u_axis, v_axis) = Vec3::orthonormal_basis(&normal);
+
+        Plane {
+            color,
+            extents: None,
+            normal,
+            point,
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+            u_axis,
+            v_axis,
+        }
+    }
+
+    // Restricts the plane to a width x height rectangle centered on its point
+    pub fn with_extents(self, width: f32, height: f32) -> Plane {
+        Plane {
+            extents: Some((width / 2.0, height / 2.0)),
+            ..self
+        }
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    pub fn get_normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn get_point(&self) -> Vec3 {
+        self.point
+    }
+
+    // Planar coordinates of a position, in world units from the plane point
+    pub fn get_uv(&self, position: &Vec3) -> (f32, f32) {
+        let offset = position - self.point;
+        (
+            Vec3::dot_product(&offset, &self.u_axis),
+            Vec3::dot_product(&offset, &self.v_axis),
+        )
+    }
+
+    fn get_hit_info(&self, position: Vec3) -> HitInfo {
+        let mut hit_info = HitInfo::new();
+        hit_info.position = position;
+        hit_info.normal = self.normal;
+        hit_info.geometric_normal = self.normal;
+        hit_info.uv = self.get_uv(&position);
+        hit_info.dpdu = self.u_axis;
+        hit_info.dpdv = self.v_axis;
+
+        hit_info
+    }
+}
+
+impl Geometry for Plane {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let denominator = Vec3::dot_product(ray.get_direction(), &self.normal);
+        if denominator.abs() < 1e-6 {
+            return None;
+        }
+
+        let distance =
+            Vec3::dot_product(&(self.point - ray.get_origin()), &self.normal) / denominator;
+        if distance < 0.0 {
+            return None;
+        }
+
+        let position = ray.point_at(distance);
+        if let Some((half_width, half_height)) = self.extents {
+            let (u, v) = self.get_uv(&position);
+            if u.abs() > half_width || v.abs() > half_height {
+                return None;
+            }
+        }
+
+        // Planes have no thickness
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = distance
+        }
+
+        if let Some(hit_info) = hitinfo {
+            *hit_info = self.get_hit_info(position);
+        }
+
+        Some(distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.get_color()
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let (half_width, half_height) = self.extents?;
+        let u = self.u_axis * half_width;
+        let v = self.v_axis * half_height;
+
+        Some(BoundingBox::from_points(&[
+            self.point - u - v,
+            self.point + u - v,
+            self.point - u + v,
+            self.point + u + v,
+        ]))
+    }
+
+    // An infinite plane bounds the half-space behind its normal, which can be used to cut shapes
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        if self.extents.is_some() {
+            return Vec::new();
+        }
+
+        let denominator = Vec3::dot_product(ray.get_direction(), &self.normal);
+        let height = Vec3::dot_product(&(ray.get_origin() - self.point), &self.normal);
+
+        let (enter_distance, exit_distance) = if denominator.abs() < 1e-6 {
+            if height > 0.0 {
+                return Vec::new();
+            }
+
+            (f32::NEG_INFINITY, f32::INFINITY)
+        } else if denominator < 0.0 {
+            (-height / denominator, f32::INFINITY)
+        } else {
+            (f32::NEG_INFINITY, -height / denominator)
+        };
+
+        vec![Interval {
+            enter: Crossing {
+                distance: enter_distance,
+                hit_info: self.get_hit_info(ray.point_at(enter_distance)),
+            },
+            exit: Crossing {
+                distance: exit_distance,
+                hit_info: self.get_hit_info(ray.point_at(exit_distance)),
+            },
+        }]
+    }
+}
@@ -52,7 +52,7 @@ impl PointLight {
 }
 
 fn reflect(r: &Vec3, n: &Vec3) -> Vec3 {
-    2.0 * n * Vec3::dot_product(&n, &r) - r
+    2.0 * n * Vec3::dot_product(n, r) - r
 }
 
 impl Light for PointLight {
@@ -62,7 +62,7 @@ impl Light for PointLight {
 
         direction.normalize_out_length(&mut length);
 
-        let diffuse_factor = Vec3::dot_product(&direction, &hit_info.normal).max(0.0);
+        let diffuse_factor = hit_info.get_diffuse_factor(&direction);
 
         let (light_color_r, light_color_g, light_color_b) = self.get_color();
 
@@ -84,7 +84,7 @@ impl Light for PointLight {
         pixel_color.b += specular_factor;
 
         if scene.intersect_dist(
-            Ray::new(hit_info.position + direction * 0.01, direction),
+            Ray::new(hit_info.position + direction * 0.01, direction).with_time(ray.get_time()),
             length,
             0.0,
         ) {
@@ -93,4 +93,34 @@ impl Light for PointLight {
             pixel_color.b *= 0.1;
         }
     }
+
+    fn get_incident_light(
+        &self,
+        scene: &Scene,
+        position: &Vec3,
+        time: f32,
+    ) -> Option<(Vec3, Color)> {
+        let mut direction = self.get_position() - position;
+        let mut length = 0.0;
+
+        direction.normalize_out_length(&mut length);
+
+        let att = (self.get_attenuation() - self.get_inv_radius() * length).max(0.0);
+        if att <= 0.0
+            || scene.intersect_dist(
+                Ray::new(position + direction * 0.01, direction).with_time(time),
+                length,
+                0.0,
+            )
+        {
+            return None;
+        }
+
+        let (light_color_r, light_color_g, light_color_b) = self.get_color();
+
+        Some((
+            direction,
+            Color::new(light_color_r, light_color_g, light_color_b) * att,
+        ))
+    }
 }
@@ -0,0 +1,663 @@
+use super::bsdf::{Bsdf, BsdfSample, Material};
+use super::color::Color;
+use super::diffuse::sample_diffuse;
+use super::geometry::HitInfo;
+use super::microfacet::{fresnel_schlick, reflect, GgxDistribution, RoughDielectric};
+use super::texture::{Channel, ChannelTexture, ConstantTexture, ScaledTexture, Texture};
+use super::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+fn grey(value: f32) -> Color {
+    Color::new(value, value, value)
+}
+
+fn constant(value: f32) -> Arc<dyn Texture> {
+    Arc::new(ConstantTexture::from_value(value))
+}
+
+fn constant_color(color: Color) -> Arc<dyn Texture> {
+    Arc::new(ConstantTexture::new(color))
+}
+
+// Factor of a material file, multiplied by its texture when there's one
+fn factor(value: Color, texture: &Option<Arc<dyn Texture>>) -> Arc<dyn Texture> {
+    match texture {
+        Some(texture) => Arc::new(ScaledTexture::new(texture.clone(), value)),
+        None => Arc::new(ConstantTexture::new(value)),
+    }
+}
+
+fn channel(texture: &Option<Arc<dyn Texture>>, channel: Channel) -> Option<Arc<dyn Texture>> {
+    texture
+        .as_ref()
+        .map(|texture| Arc::new(ChannelTexture::new(texture.clone(), channel)) as Arc<dyn Texture>)
+}
+
+// Material with the parameters artists are used to, as the Disney and OpenPBR ones. From the top: a clearcoat,
+// then either a metal, or a dielectric specular over diffuse with sheen, or transmission.
+// All parameters are textures, factors being read with `evaluate_scalar`.
+#[derive(Clone)]
+pub struct PrincipledMaterial {
+    base_color: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    clearcoat_roughness: Arc<dyn Texture>,
+    ior: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    // Cutout weight, the rest of the light going straight through as if the surface wasn't there
+    opacity: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    sheen_color: Arc<dyn Texture>,
+    // Scale of the reflectance the index of refraction gives to the dielectric specular
+    specular: Arc<dyn Texture>,
+    specular_color: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+}
+
+impl PrincipledMaterial {
+    // Rough plastic of the given color
+    pub fn new(base_color: Arc<dyn Texture>) -> PrincipledMaterial {
+        PrincipledMaterial {
+            base_color,
+            clearcoat: constant(0.0),
+            clearcoat_roughness: constant(0.03),
+            ior: constant(1.5),
+            metallic: constant(0.0),
+            opacity: constant(1.0),
+            roughness: constant(0.5),
+            sheen: constant(0.0),
+            sheen_color: constant(1.0),
+            specular: constant(1.0),
+            specular_color: constant(1.0),
+            transmission: constant(0.0),
+        }
+    }
+
+    pub fn from_gltf(material: &GltfMaterial) -> PrincipledMaterial {
+        let metallic_roughness = &material.metallic_roughness_texture;
+
+        PrincipledMaterial {
+            base_color: factor(material.base_color_factor, &material.base_color_texture),
+            clearcoat: factor(
+                grey(material.clearcoat_factor),
+                &channel(&material.clearcoat_texture, Channel::Red),
+            ),
+            clearcoat_roughness: factor(
+                grey(material.clearcoat_roughness_factor),
+                &channel(&material.clearcoat_roughness_texture, Channel::Green),
+            ),
+            ior: constant(material.ior),
+            metallic: factor(
+                grey(material.metallic_factor),
+                &channel(metallic_roughness, Channel::Blue),
+            ),
+            opacity: constant(1.0),
+            roughness: factor(
+                grey(material.roughness_factor),
+                &channel(metallic_roughness, Channel::Green),
+            ),
+            // glTF sheen only has a color
+            sheen: constant(1.0),
+            sheen_color: factor(material.sheen_color_factor, &material.sheen_color_texture),
+            specular: constant(material.specular_factor),
+            specular_color: factor(
+                material.specular_color_factor,
+                &material.specular_color_texture,
+            ),
+            transmission: factor(
+                grey(material.transmission_factor),
+                &channel(&material.transmission_texture, Channel::Red),
+            ),
+        }
+    }
+
+    pub fn from_mtl(material: &MtlMaterial) -> PrincipledMaterial {
+        // Phong exponents turned into the roughness of the lobe of about the same width
+        let roughness = material
+            .roughness
+            .unwrap_or_else(|| (2.0 / (material.shininess.max(0.0) + 2.0)).powf(0.25));
+
+        // Values of 1 and below are placeholders written by many exporters
+        let ior = if material.ior > 1.0 {
+            material.ior
+        } else {
+            1.5
+        };
+
+        // Only the illumination models with refraction let light through, tinted by the transmission filter
+        let (base_color, transmission) = match material.illum {
+            4 | 6 | 7 | 9 => (constant_color(material.transmission_filter), 1.0),
+            _ => (factor(material.diffuse, &material.diffuse_texture), 0.0),
+        };
+
+        PrincipledMaterial {
+            base_color,
+            clearcoat: constant(material.clearcoat),
+            clearcoat_roughness: constant(material.clearcoat_roughness),
+            ior: constant(ior),
+            metallic: factor(grey(material.metallic), &material.metallic_texture),
+            opacity: factor(grey(material.dissolve), &material.dissolve_texture),
+            roughness: factor(grey(roughness), &material.roughness_texture),
+            sheen: factor(grey(material.sheen), &material.sheen_texture),
+            sheen_color: constant(1.0),
+            specular: constant(1.0),
+            specular_color: factor(material.specular, &material.specular_texture),
+            transmission: constant(transmission),
+        }
+    }
+
+    pub fn with_metallic(self, metallic: Arc<dyn Texture>) -> PrincipledMaterial {
+        PrincipledMaterial { metallic, ..self }
+    }
+
+    pub fn with_roughness(self, roughness: Arc<dyn Texture>) -> PrincipledMaterial {
+        PrincipledMaterial { roughness, ..self }
+    }
+
+    pub fn with_specular(
+        self,
+        specular: Arc<dyn Texture>,
+        specular_color: Arc<dyn Texture>,
+    ) -> PrincipledMaterial {
+        PrincipledMaterial {
+            specular,
+            specular_color,
+            ..self
+        }
+    }
+
+    pub fn with_ior(self, ior: Arc<dyn Texture>) -> PrincipledMaterial {
+        PrincipledMaterial { ior, ..self }
+    }
+
+    pub fn with_sheen(
+        self,
+        sheen: Arc<dyn Texture>,
+        sheen_color: Arc<dyn Texture>,
+    ) -> PrincipledMaterial {
+        PrincipledMaterial {
+            sheen,
+            sheen_color,
+            ..self
+        }
+    }
+
+    pub fn with_clearcoat(
+        self,
+        clearcoat: Arc<dyn Texture>,
+        clearcoat_roughness: Arc<dyn Texture>,
+    ) -> PrincipledMaterial {
+        PrincipledMaterial {
+            clearcoat,
+            clearcoat_roughness,
+            ..self
+        }
+    }
+
+    pub fn with_opacity(self, opacity: Arc<dyn Texture>) -> PrincipledMaterial {
+        PrincipledMaterial { opacity, ..self }
+    }
+
+    // Base color then tints the light going through
+    pub fn with_transmission(self, transmission: Arc<dyn Texture>) -> PrincipledMaterial {
+        PrincipledMaterial {
+            transmission,
+            ..self
+        }
+    }
+
+    pub fn get_base_color(&self) -> &Arc<dyn Texture> {
+        &self.base_color
+    }
+}
+
+impl Material for PrincipledMaterial {
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Box<dyn Bsdf> {
+        let value = |texture: &Arc<dyn Texture>| texture.evaluate_scalar(hit_info).clamp(0.0, 1.0);
+
+        let base_color = self.base_color.evaluate(hit_info);
+        let roughness = value(&self.roughness);
+        let ior = self.ior.evaluate_scalar(hit_info).max(1.0);
+
+        // Reflectance of the dielectric seen straight on
+        let reflectance = ((ior - 1.0) / (ior + 1.0)).powi(2) * value(&self.specular);
+
+        Box::new(PrincipledBsdf {
+            base_color,
+            clearcoat: value(&self.clearcoat),
+            clearcoat_distribution: GgxDistribution::new(value(&self.clearcoat_roughness)),
+            dielectric: RoughDielectric::single_scattering(ior, roughness)
+                .with_transmittance(base_color),
+            distribution: GgxDistribution::new(roughness),
+            metallic: value(&self.metallic),
+            opacity: value(&self.opacity),
+            roughness,
+            sheen: self.sheen_color.evaluate(hit_info) * value(&self.sheen),
+            specular: self.specular_color.evaluate(hit_info) * reflectance,
+            transmission: value(&self.transmission),
+        })
+    }
+}
+
+// Parameters of a glTF metallic-roughness material, with the specular, ior, transmission, clearcoat and sheen
+// extensions. Textures are the ones of the file (base, specular and sheen colors loaded as sRGB, the other ones
+// as linear), their channels being picked as the specification says.
+#[derive(Clone)]
+pub struct GltfMaterial {
+    pub base_color_factor: Color,
+    pub base_color_texture: Option<Arc<dyn Texture>>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<Arc<dyn Texture>>,
+    pub specular_factor: f32,
+    pub specular_color_factor: Color,
+    pub specular_color_texture: Option<Arc<dyn Texture>>,
+    pub ior: f32,
+    pub transmission_factor: f32,
+    pub transmission_texture: Option<Arc<dyn Texture>>,
+    pub clearcoat_factor: f32,
+    pub clearcoat_texture: Option<Arc<dyn Texture>>,
+    pub clearcoat_roughness_factor: f32,
+    pub clearcoat_roughness_texture: Option<Arc<dyn Texture>>,
+    pub sheen_color_factor: Color,
+    pub sheen_color_texture: Option<Arc<dyn Texture>>,
+}
+
+// Default values of the specification
+impl Default for GltfMaterial {
+    fn default() -> GltfMaterial {
+        GltfMaterial {
+            base_color_factor: grey(1.0),
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            specular_factor: 1.0,
+            specular_color_factor: grey(1.0),
+            specular_color_texture: None,
+            ior: 1.5,
+            transmission_factor: 0.0,
+            transmission_texture: None,
+            clearcoat_factor: 0.0,
+            clearcoat_texture: None,
+            clearcoat_roughness_factor: 0.0,
+            clearcoat_roughness_texture: None,
+            sheen_color_factor: Color::black(),
+            sheen_color_texture: None,
+        }
+    }
+}
+
+// Parameters of a material of an OBJ material library, with the PBR extension (Pr, Pm, Ps, Pc and Pcr)
+#[derive(Clone)]
+pub struct MtlMaterial {
+    // Kd and map_Kd
+    pub diffuse: Color,
+    pub diffuse_texture: Option<Arc<dyn Texture>>,
+    // Ks and map_Ks
+    pub specular: Color,
+    pub specular_texture: Option<Arc<dyn Texture>>,
+    // Ns, only used without a roughness
+    pub shininess: f32,
+    // Ni
+    pub ior: f32,
+    // d and map_d, or 1 - Tr: the opacity, the rest of the surface being cut out
+    pub dissolve: f32,
+    pub dissolve_texture: Option<Arc<dyn Texture>>,
+    // Tf, tinting the light going through with the illumination models refracting it
+    pub transmission_filter: Color,
+    // illum, the lighting model from 0 to 10
+    pub illum: u32,
+    // Pr and map_Pr
+    pub roughness: Option<f32>,
+    pub roughness_texture: Option<Arc<dyn Texture>>,
+    // Pm and map_Pm
+    pub metallic: f32,
+    pub metallic_texture: Option<Arc<dyn Texture>>,
+    // Ps and map_Ps
+    pub sheen: f32,
+    pub sheen_texture: Option<Arc<dyn Texture>>,
+    // Pc and Pcr
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> MtlMaterial {
+        MtlMaterial {
+            diffuse: grey(0.8),
+            diffuse_texture: None,
+            specular: grey(1.0),
+            specular_texture: None,
+            shininess: 0.0,
+            ior: 1.5,
+            dissolve: 1.0,
+            dissolve_texture: None,
+            transmission_filter: grey(1.0),
+            illum: 2,
+            roughness: None,
+            roughness_texture: None,
+            metallic: 0.0,
+            metallic_texture: None,
+            sheen: 0.0,
+            sheen_texture: None,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Lobe {
+    Clearcoat,
+    Metal,
+    Specular,
+    Diffuse,
+    Transmission,
+}
+
+const LOBES: [Lobe; 5] = [
+    Lobe::Clearcoat,
+    Lobe::Metal,
+    Lobe::Specular,
+    Lobe::Diffuse,
+    Lobe::Transmission,
+];
+
+// Parameters of a principled material at a hit
+struct PrincipledBsdf {
+    base_color: Color,
+    clearcoat: f32,
+    clearcoat_distribution: GgxDistribution,
+    dielectric: RoughDielectric,
+    distribution: GgxDistribution,
+    metallic: f32,
+    opacity: f32,
+    roughness: f32,
+    // Sheen color times its weight
+    sheen: Color,
+    // Reflectance of the dielectric specular seen straight on
+    specular: Color,
+    transmission: f32,
+}
+
+fn evaluate_reflection(
+    distribution: &GgxDistribution,
+    reflectance: &Color,
+    wo: &Vec3,
+    wi: &Vec3,
+) -> Color {
+    if wo.z <= 0.0 || wi.z <= 0.0 {
+        return Color::black();
+    }
+
+    let mut half = wo + wi;
+    half.normalize();
+
+    let fresnel = fresnel_schlick(Vec3::dot_product(wi, &half), reflectance);
+
+    fresnel * (distribution.d(&half) * distribution.g(wo, wi) / (4.0 * wo.z * wi.z))
+}
+
+fn sample_reflection(
+    distribution: &GgxDistribution,
+    reflectance: &Color,
+    rng: &mut rand::XorShiftRng,
+    wo: &Vec3,
+) -> Option<BsdfSample> {
+    if wo.z <= 0.0 {
+        return None;
+    }
+
+    let normal = distribution.sample_visible_normal(wo, rng.next_f32(), rng.next_f32());
+    let wi = reflect(wo, &normal);
+    if wi.z <= 0.0 {
+        return None;
+    }
+
+    let fresnel = fresnel_schlick(Vec3::dot_product(wo, &normal), reflectance);
+
+    Some(BsdfSample {
+        direction: wi,
+        weight: fresnel * (distribution.g(wo, &wi) / distribution.g1(wo)),
+    })
+}
+
+impl PrincipledBsdf {
+    // Burley's diffuse, brighter at grazing angles for rough surfaces, with the sheen
+    fn evaluate_diffuse(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::black();
+        }
+
+        let mut half = wo + wi;
+        half.normalize();
+        let cos_d = Vec3::dot_product(wi, &half);
+
+        let retro_reflection = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fresnel = |cos: f32| 1.0 + (retro_reflection - 1.0) * (1.0 - cos).powi(5);
+
+        self.base_color * (fresnel(wo.z) * fresnel(wi.z) / PI) + self.sheen * (1.0 - cos_d).powi(5)
+    }
+
+    fn evaluate_lobe(&self, lobe: Lobe, wo: &Vec3, wi: &Vec3) -> Color {
+        match lobe {
+            Lobe::Clearcoat => {
+                evaluate_reflection(&self.clearcoat_distribution, &grey(0.04), wo, wi)
+            }
+            Lobe::Metal => evaluate_reflection(&self.distribution, &self.base_color, wo, wi),
+            Lobe::Specular => evaluate_reflection(&self.distribution, &self.specular, wo, wi),
+            Lobe::Diffuse => self.evaluate_diffuse(wo, wi),
+            Lobe::Transmission => self.dielectric.evaluate(wo, wi),
+        }
+    }
+
+    fn sample_lobe(
+        &self,
+        lobe: Lobe,
+        rng: &mut rand::XorShiftRng,
+        wo: &Vec3,
+    ) -> Option<BsdfSample> {
+        match lobe {
+            Lobe::Clearcoat => {
+                sample_reflection(&self.clearcoat_distribution, &grey(0.04), rng, wo)
+            }
+            Lobe::Metal => sample_reflection(&self.distribution, &self.base_color, rng, wo),
+            Lobe::Specular => sample_reflection(&self.distribution, &self.specular, rng, wo),
+            Lobe::Diffuse => sample_diffuse(rng, wo, |wi| self.evaluate_diffuse(wo, wi)),
+            Lobe::Transmission => self.dielectric.sample(rng, wo),
+        }
+    }
+
+    // Scale of each lobe, and the weight it is sampled with, about the light it reflects.
+    // Both only depend on wo, layers being attenuated by the light reflected by the ones above toward the viewer.
+    fn get_lobe_weights(&self, wo: &Vec3) -> [(f32, f32); 5] {
+        let transmission = (1.0 - self.metallic) * self.transmission;
+
+        // Only light going through can be seen from the inside
+        if wo.z <= 0.0 {
+            return [
+                (0.0, 0.0),
+                (0.0, 0.0),
+                (0.0, 0.0),
+                (0.0, 0.0),
+                (transmission, 1.0),
+            ];
+        }
+
+        let clearcoat = self.clearcoat * fresnel_schlick(wo.z, &grey(0.04)).r;
+        let base = 1.0 - clearcoat;
+
+        let metal = base * self.metallic;
+        let opaque = base * (1.0 - self.metallic) * (1.0 - self.transmission);
+        let specular = fresnel_schlick(wo.z, &self.specular).luminance();
+        let diffuse = opaque * (1.0 - specular);
+
+        [
+            (self.clearcoat, clearcoat),
+            (
+                metal,
+                metal * fresnel_schlick(wo.z, &self.base_color).luminance(),
+            ),
+            (opaque, opaque * specular),
+            (
+                diffuse,
+                diffuse * (self.base_color.luminance() + self.sheen.luminance()),
+            ),
+            (base * transmission, base * transmission),
+        ]
+    }
+}
+
+impl Bsdf for PrincipledBsdf {
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        let color = LOBES
+            .iter()
+            .zip(self.get_lobe_weights(wo).iter())
+            .filter(|(_, (scale, _))| *scale > 0.0)
+            .fold(Color::black(), |color, (lobe, (scale, _))| {
+                color + self.evaluate_lobe(*lobe, wo, wi) * *scale
+            });
+
+        color * self.opacity
+    }
+
+    // One lobe is picked, its sample being weighted by how likely it was picked.
+    // Cut out parts let the ray go on unchanged as often as they are transparent.
+    fn sample(&self, rng: &mut rand::XorShiftRng, wo: &Vec3) -> Option<BsdfSample> {
+        if self.opacity < 1.0 && rng.next_f32() >= self.opacity {
+            return Some(BsdfSample {
+                direction: -wo,
+                weight: grey(1.0),
+            });
+        }
+
+        let weights = self.get_lobe_weights(wo);
+        let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = rng.next_f32() * total;
+        let index = weights
+            .iter()
+            .position(|(_, weight)| {
+                remaining -= weight;
+                remaining < 0.0
+            })
+            .unwrap_or_else(|| {
+                weights
+                    .iter()
+                    .rposition(|(_, weight)| *weight > 0.0)
+                    .unwrap()
+            });
+
+        let (scale, weight) = weights[index];
+        let sample = self.sample_lobe(LOBES[index], rng, wo)?;
+
+        Some(BsdfSample {
+            direction: sample.direction,
+            weight: sample.weight * (scale * total / weight),
+        })
+    }
+
+    fn has_transmission(&self) -> bool {
+        self.metallic < 1.0 && self.transmission > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn scalar(texture: &Arc<dyn Texture>) -> f32 {
+        texture.evaluate_scalar(&HitInfo::new())
+    }
+
+    #[test]
+    fn gltf_metallic_and_roughness_come_from_blue_and_green() {
+        let material = PrincipledMaterial::from_gltf(&GltfMaterial {
+            metallic_factor: 0.5,
+            roughness_factor: 0.5,
+            metallic_roughness_texture: Some(Arc::new(ConstantTexture::new(Color::new(
+                0.1, 0.4, 0.8,
+            )))),
+            ..GltfMaterial::default()
+        });
+
+        assert!((scalar(&material.metallic) - 0.4).abs() < 1e-6);
+        assert!((scalar(&material.roughness) - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mtl_shininess_gives_the_roughness() {
+        let roughness = |shininess: f32| {
+            let material = PrincipledMaterial::from_mtl(&MtlMaterial {
+                shininess,
+                ..MtlMaterial::default()
+            });
+
+            scalar(&material.roughness)
+        };
+
+        assert!((roughness(0.0) - 1.0).abs() < 1e-6);
+        assert!((roughness(1000.0) - (2.0f32 / 1002.0).powf(0.25)).abs() < 1e-6);
+        assert!(roughness(10.0) > roughness(100.0));
+
+        // An explicit roughness wins over the exponent
+        let material = PrincipledMaterial::from_mtl(&MtlMaterial {
+            shininess: 1000.0,
+            roughness: Some(0.7),
+            ..MtlMaterial::default()
+        });
+        assert!((scalar(&material.roughness) - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mtl_dissolve_is_opacity_and_illum_gives_transmission() {
+        let material = PrincipledMaterial::from_mtl(&MtlMaterial {
+            dissolve: 0.25,
+            ..MtlMaterial::default()
+        });
+        assert!((scalar(&material.opacity) - 0.25).abs() < 1e-6);
+        assert_eq!(scalar(&material.transmission), 0.0);
+
+        let material = PrincipledMaterial::from_mtl(&MtlMaterial {
+            transmission_filter: Color::new(0.2, 0.4, 0.6),
+            illum: 7,
+            ..MtlMaterial::default()
+        });
+        assert_eq!(scalar(&material.opacity), 1.0);
+        assert_eq!(scalar(&material.transmission), 1.0);
+        assert!((scalar(&material.base_color) - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cut_out_surfaces_let_rays_through() {
+        let material = PrincipledMaterial::new(Arc::new(ConstantTexture::new(grey(0.8))))
+            .with_opacity(constant(0.25));
+        let bsdf = material.get_bsdf(&HitInfo::new());
+        let mut rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let count = 10000;
+        let mut through = 0;
+
+        for _ in 0..count {
+            if let Some(sample) = bsdf.sample(&mut rng, &wo) {
+                if sample.direction.z < 0.0 {
+                    assert!((sample.direction.x + wo.x).abs() < 1e-6);
+                    assert!((sample.direction.z + wo.z).abs() < 1e-6);
+                    assert_eq!(sample.weight.r, 1.0);
+                    through += 1;
+                }
+            }
+        }
+
+        let ratio = through as f32 / count as f32;
+        assert!((ratio - 0.75).abs() < 0.02, "{}", ratio);
+    }
+}
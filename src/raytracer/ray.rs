@@ -4,6 +4,9 @@ use super::vec3::Vec3;
 pub struct Ray {
     origin: Vec3,
     direction: Vec3,
+    footprint: f32,
+    spread: f32,
+    time: f32,
 }
 
 impl Ray {
@@ -14,6 +17,24 @@ impl Ray {
         Ray {
             origin,
             direction: dir,
+            footprint: 0.0,
+            spread: 0.0,
+            time: 0.0,
+        }
+    }
+
+    // Moment the ray is cast, for moving objects
+    pub fn with_time(self, time: f32) -> Ray {
+        Ray { time, ..self }
+    }
+
+    // Width of the beam the ray stands for at its origin, and how fast it grows with distance (in radians),
+    // so textures can be filtered over the area a sample covers
+    pub fn with_footprint(self, footprint: f32, spread: f32) -> Ray {
+        Ray {
+            footprint,
+            spread,
+            ..self
         }
     }
 
@@ -25,6 +46,19 @@ impl Ray {
         &self.origin
     }
 
+    // Width of the beam at the given distance
+    pub fn get_footprint(&self, distance: f32) -> f32 {
+        self.footprint + self.spread * distance
+    }
+
+    pub fn get_spread(&self) -> f32 {
+        self.spread
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
     pub fn point_at(&self, t: f32) -> Vec3 {
         self.origin + t * self.direction
     }
@@ -0,0 +1,406 @@
+use super::camera::Camera;
+use super::color::Color;
+use super::film::Film;
+use super::scene::Scene;
+use super::tile::Tile;
+use rand::{Rng, SeedableRng};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use threadpool::ThreadPool;
+
+#[derive(Copy, Clone, Debug)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub tile_size: usize,
+    pub max_iteration: u32,
+    // Without a sample count, passes go on until the time budget runs out (or forever)
+    pub samples_per_pixel: Option<u32>,
+    // Refines the whole frame pass by pass (1, 2, 4, ... samples) instead of rendering each tile at full quality
+    pub progressive: bool,
+    // Passes are sized to fit in the budget, tiles still rendering when it expires are dropped (even in the first
+    // pass, which may leave parts of the frame black)
+    pub time_budget: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+pub struct RenderProgress {
+    pub updated_tiles: Vec<Tile>,
+    pub pass_completed: bool,
+    pub finished: bool,
+}
+
+struct TileResult {
+    tile_index: usize,
+    sample_count: u32,
+    colors: Option<Vec<Color>>,
+}
+
+pub struct Renderer {
+    camera: Arc<dyn Camera>,
+    scene: Arc<Scene>,
+    settings: RenderSettings,
+    tiles: Vec<Tile>,
+    pool: ThreadPool,
+    sender: Sender<TileResult>,
+    receiver: Receiver<TileResult>,
+    tile_samples: Vec<u32>,
+    pending_tiles: usize,
+    samples_done: u32,
+    pass_start: Instant,
+    pass_sample_count: u32,
+    sample_duration: Option<f32>,
+    deadline: Option<Instant>,
+    finished: bool,
+}
+
+// Seeds are derived from the tile and its first sample so a pass always draws the same random numbers
+fn sample_seed(tile_index: usize, first_sample: u32) -> [u32; 4] {
+    let mut state = ((tile_index as u64) << 32) | first_sample as u64;
+    let mut seed = [0u32; 4];
+    for value in seed.iter_mut() {
+        // SplitMix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        *value = (z ^ (z >> 31)) as u32;
+    }
+
+    seed
+}
+
+fn render_tile(
+    camera: &dyn Camera,
+    scene: &Scene,
+    settings: &RenderSettings,
+    tile: &Tile,
+    first_sample: u32,
+    sample_count: u32,
+    deadline: Option<Instant>,
+) -> Option<Vec<Color>> {
+    let mut rng = rand::XorShiftRng::from_seed(sample_seed(tile.index, first_sample));
+
+    let mut colors = vec![
+        Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        };
+        tile.get_pixel_count()
+    ];
+
+    for y in 0..tile.height {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+
+        let screen_y = (tile.min_y + y) as f32;
+        for x in 0..tile.width {
+            let screen_x = (tile.min_x + x) as f32;
+
+            let color = &mut colors[y * tile.width + x];
+            for _ in 0..sample_count {
+                let (offset_x, offset_y) = if settings.samples_per_pixel != Some(1) {
+                    (rng.next_f32() * 2.0 - 1.0, rng.next_f32() * 2.0 - 1.0)
+                } else {
+                    (0.0, 0.0)
+                };
+
+                let factor_x = (screen_x + offset_x) / settings.width as f32;
+                let factor_y = (screen_y + offset_y) / settings.height as f32;
+
+                let ray_rng = rng.clone();
+
+                if let Some(ray) = camera.get_ray(&mut rng, factor_x, factor_y) {
+                    let spread = camera.get_spread(
+                        &ray_rng,
+                        &ray,
+                        factor_x,
+                        factor_y,
+                        1.0 / settings.width as f32,
+                    );
+                    let ray = ray.with_footprint(0.0, spread);
+
+                    let (_, _, trace_color) =
+                        scene.trace(&mut rng, ray, settings.max_iteration, 0f32);
+
+                    color.r += trace_color.r;
+                    color.g += trace_color.g;
+                    color.b += trace_color.b;
+                }
+            }
+        }
+    }
+
+    Some(colors)
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings, camera: Arc<dyn Camera>, scene: Arc<Scene>) -> Renderer {
+        let pool = threadpool::Builder::new()
+            .thread_name(String::from("Raytracer"))
+            .build();
+
+        let (sender, receiver) = channel();
+
+        let tiles = Tile::split(settings.width, settings.height, settings.tile_size);
+        let tile_samples = vec![0; tiles.len()];
+
+        Renderer {
+            camera,
+            scene,
+            settings,
+            tiles,
+            tile_samples,
+            pool,
+            sender,
+            receiver,
+            pending_tiles: 0,
+            samples_done: 0,
+            pass_start: Instant::now(),
+            pass_sample_count: 0,
+            sample_duration: None,
+            deadline: None,
+            finished: false,
+        }
+    }
+
+    pub fn get_samples_done(&self) -> u32 {
+        self.samples_done
+    }
+
+    pub fn get_settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Starts rendering from the samples already accumulated in the film, which may come from a checkpoint
+    pub fn start(&mut self, film: &Film) {
+        for (tile, samples) in self.tiles.iter().zip(self.tile_samples.iter_mut()) {
+            *samples = film.get_sample_count(tile.min_x, tile.min_y);
+        }
+
+        self.samples_done = self.tile_samples.iter().copied().min().unwrap_or(0);
+        self.deadline = self
+            .settings
+            .time_budget
+            .map(|budget| Instant::now() + budget);
+
+        self.dispatch_pass();
+    }
+
+    // Merges finished tiles into the film and schedules the next pass once the current one is over
+    pub fn update(&mut self, film: &mut Film) -> RenderProgress {
+        let mut progress = RenderProgress::default();
+        let mut received = false;
+
+        for result in self.receiver.try_iter() {
+            received = true;
+            self.pending_tiles -= 1;
+
+            // Tiles dropped because of the time budget don't bring anything
+            if let Some(colors) = result.colors {
+                let tile = self.tiles[result.tile_index];
+                film.add_samples(&tile, &colors, result.sample_count);
+                progress.updated_tiles.push(tile);
+
+                self.tile_samples[result.tile_index] += result.sample_count;
+            }
+        }
+
+        if received && self.pending_tiles == 0 {
+            progress.pass_completed = true;
+            self.samples_done = self.tile_samples.iter().copied().min().unwrap_or(0);
+            self.sample_duration =
+                Some(self.pass_start.elapsed().as_secs_f32() / self.pass_sample_count as f32);
+
+            self.dispatch_pass();
+            progress.finished = self.finished;
+        }
+
+        progress
+    }
+
+    fn next_pass_sample_count(&self) -> u32 {
+        let mut sample_count = match self.settings.samples_per_pixel {
+            Some(samples_per_pixel) if !self.settings.progressive => {
+                samples_per_pixel.saturating_sub(self.samples_done)
+            }
+            Some(samples_per_pixel) => {
+                // Each pass doubles the sample count of the frame
+                self.samples_done
+                    .max(1)
+                    .min(samples_per_pixel.saturating_sub(self.samples_done))
+            }
+            None => self.samples_done.max(1),
+        };
+
+        if let (Some(deadline), Some(sample_duration)) = (self.deadline, self.sample_duration) {
+            // Only start what has a chance to be done before the deadline, based on the previous pass
+            let time_left = deadline
+                .saturating_duration_since(Instant::now())
+                .as_secs_f32();
+
+            sample_count = sample_count.min((time_left / sample_duration) as u32);
+        }
+
+        sample_count
+    }
+
+    // Only tiles lagging behind are rendered, so a pass interrupted by a checkpoint is completed first
+    fn dispatch_pass(&mut self) {
+        let first_sample = self.samples_done;
+        let sample_count = self.next_pass_sample_count();
+
+        self.pending_tiles = 0;
+        self.pass_start = Instant::now();
+        self.pass_sample_count = sample_count;
+        self.finished = sample_count == 0;
+        if self.finished {
+            return;
+        }
+
+        for (tile, samples) in self.tiles.iter().zip(self.tile_samples.iter()) {
+            if *samples != first_sample {
+                continue;
+            }
+
+            let camera = self.camera.clone();
+            let scene = self.scene.clone();
+            let settings = self.settings;
+            let tile = *tile;
+            let sender = self.sender.clone();
+            let deadline = self.deadline;
+
+            self.pool.execute(move || {
+                let colors = render_tile(
+                    camera.as_ref(),
+                    &scene,
+                    &settings,
+                    &tile,
+                    first_sample,
+                    sample_count,
+                    deadline,
+                );
+
+                // The renderer may have been dropped in the meantime, in which case the result is useless
+                let _ = sender.send(TileResult {
+                    tile_index: tile.index,
+                    sample_count,
+                    colors,
+                });
+            });
+
+            self.pending_tiles += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::perspective_camera::PerspectiveCamera;
+    use crate::raytracer::point_light::PointLight;
+    use crate::raytracer::sphere::Sphere;
+    use crate::raytracer::vec3::Vec3;
+    use std::thread;
+
+    fn renderer(settings: RenderSettings) -> Renderer {
+        let camera = PerspectiveCamera::new(
+            Vec3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 0.5, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            settings.width as f32 / settings.height as f32,
+            90.0,
+            0.0,
+            2.0,
+        );
+
+        let mut scene = Scene::new();
+        scene.add_light(Box::new(PointLight::new(
+            Vec3::new(0.0, 1.5, -1.0),
+            (1.0, 1.0, 1.0),
+            0.9,
+            15.0,
+        )));
+        scene.add_object(Box::new(Sphere::new(
+            Vec3::new(0.0, 0.5, -1.5),
+            0.5,
+            Color::new(1.0, 0.5, 0.0),
+            0.5,
+            0.0,
+        )));
+
+        Renderer::new(settings, Arc::new(camera), Arc::new(scene))
+    }
+
+    fn settings() -> RenderSettings {
+        RenderSettings {
+            width: 24,
+            height: 16,
+            tile_size: 8,
+            max_iteration: 3,
+            samples_per_pixel: Some(16),
+            progressive: true,
+            time_budget: None,
+        }
+    }
+
+    // Renders until the end, returning the samples per pixel after each pass
+    fn render(renderer: &mut Renderer, film: &mut Film) -> Vec<u32> {
+        let mut passes = Vec::new();
+
+        renderer.start(film);
+        while !renderer.is_finished() {
+            if renderer.update(film).pass_completed {
+                passes.push(renderer.get_samples_done());
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        passes
+    }
+
+    #[test]
+    fn progressive_passes_double_the_samples() {
+        let settings = settings();
+        let mut film = Film::new(settings.width, settings.height);
+
+        assert_eq!(
+            render(&mut renderer(settings), &mut film),
+            vec![1, 2, 4, 8, 16]
+        );
+        assert_eq!(film.get_average_sample_count(), 16.0);
+    }
+
+    #[test]
+    fn progressive_passes_stop_at_the_sample_count() {
+        let settings = RenderSettings {
+            samples_per_pixel: Some(5),
+            ..settings()
+        };
+        let mut film = Film::new(settings.width, settings.height);
+
+        assert_eq!(render(&mut renderer(settings), &mut film), vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn expired_budget_stops_the_first_pass() {
+        let settings = RenderSettings {
+            samples_per_pixel: None,
+            time_budget: Some(Duration::from_secs(0)),
+            ..settings()
+        };
+        let mut film = Film::new(settings.width, settings.height);
+
+        render(&mut renderer(settings), &mut film);
+
+        assert_eq!(film.get_average_sample_count(), 0.0);
+    }
+}
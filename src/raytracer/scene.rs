@@ -1,9 +1,11 @@
+use super::bsdf::Bsdf;
 use super::color::Color;
 use super::geometry::{Geometry, HitInfo};
 use super::light::Light;
 use super::ray::Ray;
 use super::vec3::Vec3;
 use rand::Rng;
+use std::f32::consts::PI;
 use std::mem;
 
 pub struct Scene {
@@ -30,11 +32,11 @@ fn background_color(ray: &Ray) -> Color {
 }
 
 fn reflect(r: &Vec3, n: &Vec3) -> Vec3 {
-    2.0 * n * Vec3::dot_product(&n, &r) - r
+    2.0 * n * Vec3::dot_product(n, r) - r
 }
 
 fn refract(i: &Vec3, n: &Vec3, refractive_index: f32) -> Option<Vec3> {
-    let dt = Vec3::dot_product(&i, &n);
+    let dt = Vec3::dot_product(i, n);
     let discriminant = 1.0 - refractive_index * refractive_index * (1.0 - dt * dt);
     if discriminant > 0.0 {
         Some(refractive_index * (i - n * dt) - n * discriminant.sqrt())
@@ -50,8 +52,10 @@ fn schlick(cosine: f32, refractive_index: f32) -> f32 {
     r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0)
 }
 
+// Exact Fresnel factor, only used by the commented out refraction below which Schlick's approximation replaced
+#[allow(dead_code)]
 fn fresnel(i: &Vec3, n: &Vec3, refractive_index: f32) -> f32 {
-    let mut cosi = Vec3::dot_product(i, n).max(-1.0).min(1.0);
+    let mut cosi = Vec3::dot_product(i, n).clamp(-1.0, 1.0);
     let mut etai = 1.0;
     let mut etat = refractive_index;
 
@@ -72,6 +76,12 @@ fn fresnel(i: &Vec3, n: &Vec3, refractive_index: f32) -> f32 {
     }
 }
 
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
+}
+
 impl Scene {
     pub fn new() -> Scene {
         Scene {
@@ -89,19 +99,16 @@ impl Scene {
     }
 
     pub fn intersect(&self, ray: Ray) -> Option<f32> {
-        let mut closest_distance = std::f32::INFINITY;
-        for (i, object) in self.objects.iter().enumerate() {
-            match object.compute_hit(&ray, None, None) {
-                Some(dist) => {
-                    if dist < closest_distance {
-                        closest_distance = dist
-                    }
+        let mut closest_distance = f32::INFINITY;
+        for object in self.objects.iter() {
+            if let Some(dist) = object.compute_hit(&ray, None, None) {
+                if dist < closest_distance {
+                    closest_distance = dist
                 }
-                None => (),
             }
         }
 
-        if closest_distance < std::f32::INFINITY {
+        if closest_distance < f32::INFINITY {
             Some(closest_distance)
         } else {
             None
@@ -109,20 +116,82 @@ impl Scene {
     }
 
     pub fn intersect_dist(&self, ray: Ray, dist: f32, min_dist: f32) -> bool {
-        for (i, object) in self.objects.iter().enumerate() {
-            match object.compute_hit(&ray, None, None) {
-                Some(hit_distance) => {
-                    if hit_distance < dist && hit_distance > min_dist {
-                        return true;
-                    }
+        for object in self.objects.iter() {
+            if let Some(hit_distance) = object.compute_hit(&ray, None, None) {
+                if hit_distance < dist && hit_distance > min_dist {
+                    return true;
                 }
-                None => (),
             }
         }
 
         false
     }
 
+    // Lighting of a surface shaded by a BSDF: direct light from each light, and one sampled bounce
+    // for the light coming from everything else
+    fn shade(
+        &self,
+        rng: &mut rand::XorShiftRng,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        bsdf: &dyn Bsdf,
+        max_iter: u32,
+    ) -> Color {
+        let (tangent, mut bitangent, mut normal) = hit_info.get_tangent_frame();
+
+        // Surfaces without an inside are shaded on the side they are seen from
+        if !bsdf.has_transmission()
+            && Vec3::dot_product(ray.get_direction(), &hit_info.geometric_normal) > 0.0
+        {
+            bitangent = -bitangent;
+            normal = -normal;
+        }
+
+        let to_local = |direction: &Vec3| {
+            Vec3::new(
+                Vec3::dot_product(direction, &tangent),
+                Vec3::dot_product(direction, &bitangent),
+                Vec3::dot_product(direction, &normal),
+            )
+        };
+        let wo = to_local(&-ray.get_direction());
+
+        let mut color = Color::black();
+
+        // Light colors are the brightness they give to a white matte surface facing them, hence the pi
+        for light in self.lights.iter() {
+            if let Some((direction, light_color)) =
+                light.get_incident_light(self, &hit_info.position, ray.get_time())
+            {
+                let wi = to_local(&direction);
+                color = color + bsdf.evaluate(&wo, &wi) * light_color * (PI * wi.z.abs());
+            }
+        }
+
+        if max_iter > 0 {
+            if let Some(sample) = bsdf.sample(rng, &wo) {
+                let direction = tangent * sample.direction.x
+                    + bitangent * sample.direction.y
+                    + normal * sample.direction.z;
+
+                let (_, _, traced_color) = self.trace(
+                    rng,
+                    Ray::new(hit_info.position + direction * 0.01, direction)
+                        .with_time(ray.get_time())
+                        .with_footprint(hit_info.footprint, ray.get_spread()),
+                    max_iter - 1,
+                    0f32,
+                );
+
+                color = color + sample.weight * traced_color;
+            }
+        }
+
+        color
+    }
+
+    // Distance of the closest hit, where the ray leaves the hit object (for transparent ones only, infinite
+    // otherwise) and color seen along the ray
     pub fn trace(
         &self,
         rng: &mut rand::XorShiftRng,
@@ -131,43 +200,56 @@ impl Scene {
         min_dist: f32,
     ) -> (f32, f32, Color) {
         let mut closest_object: Option<usize> = None;
-        let mut closest_distance = std::f32::INFINITY;
-        let mut closest_exit_distance = std::f32::INFINITY;
-        let mut closest_hitinfo = HitInfo {
-            position: Vec3::zero(),
-            normal: Vec3::zero(),
-        };
+        let mut closest_distance = f32::INFINITY;
+        let mut closest_exit_distance = f32::INFINITY;
+        let mut closest_hitinfo = HitInfo::new();
 
         for (i, object) in self.objects.iter().enumerate() {
-            let mut hit_info = HitInfo {
-                position: Vec3::zero(),
-                normal: Vec3::zero(),
-            };
-
-            let mut exit_dist = 0f32;
-            match object.compute_hit(&ray, Some(&mut hit_info), Some(&mut exit_dist)) {
-                Some(distance) => {
-                    if distance < closest_distance && distance > min_dist {
-                        closest_distance = distance;
-                        closest_hitinfo = hit_info;
-                        closest_exit_distance = exit_dist;
-                        closest_object = Some(i);
-                    }
+            let mut hit_info = HitInfo::new();
+
+            if let Some(distance) = object.compute_hit(&ray, Some(&mut hit_info), None) {
+                if distance < closest_distance && distance > min_dist {
+                    closest_distance = distance;
+                    closest_hitinfo = hit_info;
+                    closest_object = Some(i);
                 }
-                _ => (),
             }
         }
 
+        // Where the ray leaves the closest object is only needed when it refracts, and can be costly to find
+        let exit_distance = |object: &dyn Geometry| {
+            let mut exit_dist = f32::INFINITY;
+            object.compute_hit(&ray, None, Some(&mut exit_dist));
+
+            exit_dist
+        };
+
         match closest_object {
             Some(object) => {
                 let object = &self.objects[object];
 
-                let object_color = object.get_color(&closest_hitinfo.position);
-
-                let reflection_factor = object.get_reflection_factor();
-                let transparency_factor = object.get_transparency_factor();
+                closest_hitinfo.footprint = ray.get_footprint(closest_distance);
 
                 closest_hitinfo.normal.normalize();
+                closest_hitinfo.geometric_normal.normalize();
+                closest_hitinfo.normal = object.get_shading_normal(&closest_hitinfo);
+
+                if let Some(bsdf) = object.get_bsdf(&closest_hitinfo) {
+                    if bsdf.has_transmission() {
+                        closest_exit_distance = exit_distance(object.as_ref());
+                    }
+
+                    let color = self.shade(rng, &ray, &closest_hitinfo, bsdf.as_ref(), max_iter);
+                    return (closest_distance, closest_exit_distance, color);
+                }
+
+                let object_color = object.get_color(&closest_hitinfo);
+
+                let reflection_factor = object.get_reflection_factor(&closest_hitinfo);
+                let transparency_factor = object.get_transparency_factor(&closest_hitinfo);
+                if transparency_factor.is_some() {
+                    closest_exit_distance = exit_distance(object.as_ref());
+                }
 
                 let mut color = Color {
                     r: 0.0,
@@ -191,9 +273,9 @@ impl Scene {
                                     };
                         }
 
-                        let mut final_color_r = object_color.r as f32 * (1.0 - reflection_factor);
-                        let mut final_color_g = object_color.g as f32 * (1.0 - reflection_factor);
-                        let mut final_color_b = object_color.b as f32 * (1.0 - reflection_factor);
+                        let mut final_color_r = object_color.r * (1.0 - reflection_factor);
+                        let mut final_color_g = object_color.g * (1.0 - reflection_factor);
+                        let mut final_color_b = object_color.b * (1.0 - reflection_factor);
 
                         if max_iter > 0 {
                             let reflection_origin =
@@ -205,14 +287,16 @@ impl Scene {
 
                             let (hit, _, reflected_color) = self.trace(
                                 rng,
-                                Ray::new(reflection_origin, reflection),
+                                Ray::new(reflection_origin, reflection)
+                                    .with_time(ray.get_time())
+                                    .with_footprint(closest_hitinfo.footprint, ray.get_spread()),
                                 max_iter - 1,
                                 0f32,
                             );
 
-                            final_color_r += reflected_color.r as f32 * reflection_factor;
-                            final_color_g += reflected_color.g as f32 * reflection_factor;
-                            final_color_b += reflected_color.b as f32 * reflection_factor;
+                            final_color_r += reflected_color.r * reflection_factor;
+                            final_color_g += reflected_color.g * reflection_factor;
+                            final_color_b += reflected_color.b * reflection_factor;
                         }
 
                         color.r = final_color_r;
@@ -232,7 +316,7 @@ impl Scene {
                         let cosine;
 
                         let dot_ray_normal =
-                            Vec3::dot_product(&ray.get_direction(), &closest_hitinfo.normal);
+                            Vec3::dot_product(ray.get_direction(), &closest_hitinfo.normal);
                         if dot_ray_normal > 0.0 {
                             outward_normal = -outward_normal;
                             cosine = refractive_index * dot_ray_normal;
@@ -241,55 +325,56 @@ impl Scene {
                             cosine = -dot_ray_normal;
                         }
 
-                        match refract(&ray.get_direction(), &outward_normal, refractive_index) {
-                            Some(mut refraction_dir) => {
-                                refraction_dir.normalize();
-
-                                let (hit, _, refracted_color) = self.trace(
-                                    rng,
-                                    Ray::new(closest_hitinfo.position, refraction_dir),
-                                    max_iter - 1,
-                                    0.01,
-                                );
-
-                                let reflect_prob = schlick(cosine, refractive_index);
-                                color.r = color.r * reflect_prob
-                                    + refracted_color.r * (1.0 - reflect_prob);
-                                color.g = color.g * reflect_prob
-                                    + refracted_color.g * (1.0 - reflect_prob);
-                                color.b = color.b * reflect_prob
-                                    + refracted_color.b * (1.0 - reflect_prob);
-                                /*let refraction_origin =
-                                    if Vec3::dot_product(&refraction_dir, &closest_hitinfo.normal)
-                                        < 0.0
-                                    {
-                                        closest_hitinfo.position - refraction_dir * 0.01
-                                    } else {
-                                        closest_hitinfo.position + refraction_dir * 0.01
-                                    };
+                        if let Some(mut refraction_dir) =
+                            refract(ray.get_direction(), &outward_normal, refractive_index)
+                        {
+                            refraction_dir.normalize();
+
+                            let (hit, _, refracted_color) = self.trace(
+                                rng,
+                                Ray::new(closest_hitinfo.position, refraction_dir)
+                                    .with_time(ray.get_time())
+                                    .with_footprint(closest_hitinfo.footprint, ray.get_spread()),
+                                max_iter - 1,
+                                0.01,
+                            );
+
+                            let reflect_prob = schlick(cosine, refractive_index);
+                            color.r =
+                                color.r * reflect_prob + refracted_color.r * (1.0 - reflect_prob);
+                            color.g =
+                                color.g * reflect_prob + refracted_color.g * (1.0 - reflect_prob);
+                            color.b =
+                                color.b * reflect_prob + refracted_color.b * (1.0 - reflect_prob);
+                            /*let refraction_origin =
+                                if Vec3::dot_product(&refraction_dir, &closest_hitinfo.normal)
+                                    < 0.0
+                                {
+                                    closest_hitinfo.position - refraction_dir * 0.01
+                                } else {
+                                    closest_hitinfo.position + refraction_dir * 0.01
+                                };
 
-                                let (hit, _, refracted_r, refracted_g, refracted_b) = self.trace(
-                                    Ray::new(refraction_origin, refraction_dir),
-                                    max_iter - 1,
-                                    0.01,
-                                );
-
-                                let fresnel_factor = fresnel(
-                                    &ray.get_direction(),
-                                    &closest_hitinfo.normal,
-                                    refractive_index,
-                                );
-
-                                r = r * fresnel_factor + refracted_r * (1.0 - fresnel_factor);
-                                g = g * fresnel_factor + refracted_g * (1.0 - fresnel_factor);
-                                b = b * fresnel_factor + refracted_b * (1.0 - fresnel_factor);*/
-                            }
-                            _ => (),
+                            let (hit, _, refracted_r, refracted_g, refracted_b) = self.trace(
+                                Ray::new(refraction_origin, refraction_dir),
+                                max_iter - 1,
+                                0.01,
+                            );
+
+                            let fresnel_factor = fresnel(
+                                &ray.get_direction(),
+                                &closest_hitinfo.normal,
+                                refractive_index,
+                            );
+
+                            r = r * fresnel_factor + refracted_r * (1.0 - fresnel_factor);
+                            g = g * fresnel_factor + refracted_g * (1.0 - fresnel_factor);
+                            b = b * fresnel_factor + refracted_b * (1.0 - fresnel_factor);*/
                         }
                     }
 
                     for light in self.lights.iter() {
-                        light.compute_light(&self, &closest_hitinfo, &mut color, &ray);
+                        light.compute_light(self, &closest_hitinfo, &mut color, &ray);
                     }
                 }
 
@@ -302,3 +387,81 @@ impl Scene {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::bounds::BoundingBox;
+    use crate::raytracer::sphere::Sphere;
+    use rand::SeedableRng;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Sphere counting how many times it is asked where rays leave it
+    struct ExitCounter {
+        sphere: Sphere,
+        exit_requests: Arc<AtomicUsize>,
+    }
+
+    impl Geometry for ExitCounter {
+        fn compute_hit(
+            &self,
+            ray: &Ray,
+            hitinfo: Option<&mut HitInfo>,
+            exit_dist: Option<&mut f32>,
+        ) -> Option<f32> {
+            if exit_dist.is_some() {
+                self.exit_requests.fetch_add(1, Ordering::SeqCst);
+            }
+
+            self.sphere.compute_hit(ray, hitinfo, exit_dist)
+        }
+
+        fn get_color(&self, hit_info: &HitInfo) -> Color {
+            self.sphere.get_color()
+        }
+
+        fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+            self.sphere.get_reflection_factor(hit_info)
+        }
+
+        fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+            self.sphere.get_transparency_factor(hit_info)
+        }
+
+        fn get_bounds(&self) -> Option<BoundingBox> {
+            self.sphere.get_bounds()
+        }
+    }
+
+    fn trace_through(transparency_factor: f32) -> (f32, usize) {
+        let exit_requests = Arc::new(AtomicUsize::new(0));
+
+        let mut scene = Scene::new();
+        scene.add_object(Box::new(ExitCounter {
+            sphere: Sphere::new(
+                Vec3::new(0.0, 0.0, -3.0),
+                1.0,
+                Color::new(1.0, 1.0, 1.0),
+                0.0,
+                transparency_factor,
+            ),
+            exit_requests: exit_requests.clone(),
+        }));
+
+        let mut rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+        let ray = Ray::new(Vec3::zero(), Vec3::new(0.0, 0.0, -1.0));
+        let (_, exit_distance, _) = scene.trace(&mut rng, ray, 0, 0.0);
+
+        (exit_distance, exit_requests.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn exit_distance_is_only_found_for_transparent_objects() {
+        assert_eq!(trace_through(0.0), (f32::INFINITY, 0));
+
+        let (exit_distance, exit_requests) = trace_through(0.5);
+        assert!((exit_distance - 4.0).abs() < 1e-4);
+        assert_eq!(exit_requests, 1);
+    }
+}
@@ -0,0 +1,156 @@
+use super::geometry::Geometry;
+use super::instance::Instance;
+use super::ray::Ray;
+use super::scene::Scene;
+use super::transform::Transform;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+// A node without geometry is a group, only used to move its children together
+struct SceneNode {
+    name: String,
+    transform: Transform,
+    geometry: Option<Arc<dyn Geometry>>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+// Hierarchy of named nodes, each transform being relative to the parent node
+pub struct SceneGraph {
+    nodes: Vec<SceneNode>,
+}
+
+impl Default for SceneGraph {
+    fn default() -> SceneGraph {
+        SceneGraph::new()
+    }
+}
+
+impl SceneGraph {
+    pub fn new() -> SceneGraph {
+        SceneGraph { nodes: Vec::new() }
+    }
+
+    fn add_node(
+        &mut self,
+        parent: Option<NodeId>,
+        name: &str,
+        transform: Transform,
+        geometry: Option<Arc<dyn Geometry>>,
+    ) -> NodeId {
+        let id = NodeId(self.nodes.len());
+
+        self.nodes.push(SceneNode {
+            name: String::from(name),
+            transform,
+            geometry,
+            parent,
+            children: Vec::new(),
+        });
+
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+
+        id
+    }
+
+    pub fn add_group(
+        &mut self,
+        parent: Option<NodeId>,
+        name: &str,
+        transform: Transform,
+    ) -> NodeId {
+        self.add_node(parent, name, transform, None)
+    }
+
+    pub fn add_object(
+        &mut self,
+        parent: Option<NodeId>,
+        name: &str,
+        transform: Transform,
+        geometry: Arc<dyn Geometry>,
+    ) -> NodeId {
+        self.add_node(parent, name, transform, Some(geometry))
+    }
+
+    // Returns the first node with this name, in creation order
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.name == name)
+            .map(NodeId)
+    }
+
+    pub fn get_name(&self, id: NodeId) -> &str {
+        &self.nodes[id.0].name
+    }
+
+    pub fn get_parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn get_children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+
+    pub fn get_geometry(&self, id: NodeId) -> Option<&Arc<dyn Geometry>> {
+        self.nodes[id.0].geometry.as_ref()
+    }
+
+    pub fn get_transform(&self, id: NodeId) -> &Transform {
+        &self.nodes[id.0].transform
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        self.nodes[id.0].transform = transform;
+    }
+
+    pub fn set_geometry(&mut self, id: NodeId, geometry: Option<Arc<dyn Geometry>>) {
+        self.nodes[id.0].geometry = geometry;
+    }
+
+    // Transform from the node space to the world space, going through all its parents
+    pub fn get_world_transform(&self, id: NodeId) -> Transform {
+        let node = &self.nodes[id.0];
+        match node.parent {
+            Some(parent) => self.get_world_transform(parent) * node.transform,
+            None => node.transform,
+        }
+    }
+
+    fn instances(&self) -> impl Iterator<Item = (NodeId, Instance)> + '_ {
+        self.nodes.iter().enumerate().filter_map(move |(i, node)| {
+            node.geometry.as_ref().map(|geometry| {
+                let id = NodeId(i);
+                (
+                    id,
+                    Instance::new(geometry.clone(), self.get_world_transform(id)),
+                )
+            })
+        })
+    }
+
+    // Closest object hit by the ray, if any
+    pub fn pick(&self, ray: &Ray) -> Option<NodeId> {
+        let mut closest: Option<(NodeId, f32)> = None;
+        for (id, instance) in self.instances() {
+            if let Some(distance) = instance.compute_hit(ray, None, None) {
+                if closest.map_or(true, |(_, closest_distance)| distance < closest_distance) {
+                    closest = Some((id, distance));
+                }
+            }
+        }
+
+        closest.map(|(id, _)| id)
+    }
+
+    // Flattens the graph into the scene, each object becoming an instance placed in world space
+    pub fn add_to_scene(&self, scene: &mut Scene) {
+        for (_, instance) in self.instances() {
+            scene.add_object(Box::new(instance));
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::{Geometry, HitInfo};
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+// Signed distance function, negative inside the shape.
+// Primitives are centered on the origin and placed with `translate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sdf {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_size: Vec3,
+    },
+    RoundBox {
+        half_size: Vec3,
+        radius: f32,
+    },
+    // Around the y axis
+    Torus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    Capsule {
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+    },
+    // Half-space below the plane
+    Plane {
+        normal: Vec3,
+        offset: f32,
+    },
+    Mandelbulb {
+        power: f32,
+        iterations: u32,
+    },
+    Translate(Vec3, Box<Sdf>),
+    Scale(f32, Box<Sdf>),
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    Difference(Box<Sdf>, Box<Sdf>),
+    // Blends both shapes over a distance of `smoothness`
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    // Infinite copies every period along each axis, a null period disabling the repetition
+    Repeat(Vec3, Box<Sdf>),
+    // Rotates around the y axis by `rate` radians per unit of height
+    Twist(f32, Box<Sdf>),
+    // Sine wave bumps of the given amplitude and frequency
+    Displace(f32, f32, Box<Sdf>),
+}
+
+fn abs(vector: &Vec3) -> Vec3 {
+    Vec3::new(vector.x.abs(), vector.y.abs(), vector.z.abs())
+}
+
+fn max_zero(vector: &Vec3) -> Vec3 {
+    Vec3::new(vector.x.max(0.0), vector.y.max(0.0), vector.z.max(0.0))
+}
+
+fn box_distance(point: &Vec3, half_size: &Vec3) -> f32 {
+    let q = abs(point) - half_size;
+    max_zero(&q).length() + q.x.max(q.y).max(q.z).min(0.0)
+}
+
+fn repeat(value: f32, period: f32) -> f32 {
+    if period > 0.0 {
+        value - period * (value / period).round()
+    } else {
+        value
+    }
+}
+
+fn mandelbulb_distance(point: &Vec3, power: f32, iterations: u32) -> f32 {
+    let mut z = *point;
+    let mut derivative = 1.0;
+    let mut radius = 0.0;
+
+    for _ in 0..iterations {
+        radius = z.length();
+        if radius > 2.0 {
+            break;
+        }
+
+        // z to the power vanishes, without any angle
+        if radius == 0.0 {
+            derivative = 1.0;
+            z = *point;
+            continue;
+        }
+
+        let theta = (z.z / radius).clamp(-1.0, 1.0).acos() * power;
+        let phi = z.y.atan2(z.x) * power;
+        derivative = radius.powf(power - 1.0) * power * derivative + 1.0;
+
+        let scaled_radius = radius.powf(power);
+        z = scaled_radius
+            * Vec3::new(
+                theta.sin() * phi.cos(),
+                phi.sin() * theta.sin(),
+                theta.cos(),
+            )
+            + point;
+    }
+
+    // Points staying at the origin are inside the set
+    if radius == 0.0 {
+        return 0.0;
+    }
+
+    0.5 * radius.ln() * radius / derivative
+}
+
+impl Sdf {
+    pub fn sphere(radius: f32) -> Sdf {
+        Sdf::Sphere { radius }
+    }
+
+    pub fn cuboid(size: Vec3) -> Sdf {
+        Sdf::Box {
+            half_size: size * 0.5,
+        }
+    }
+
+    pub fn round_cuboid(size: Vec3, radius: f32) -> Sdf {
+        Sdf::RoundBox {
+            half_size: size * 0.5,
+            radius,
+        }
+    }
+
+    pub fn torus(major_radius: f32, minor_radius: f32) -> Sdf {
+        Sdf::Torus {
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    pub fn capsule(start: Vec3, end: Vec3, radius: f32) -> Sdf {
+        Sdf::Capsule { start, end, radius }
+    }
+
+    pub fn plane(normal: Vec3, offset: f32) -> Sdf {
+        let mut normal = normal;
+        normal.normalize();
+
+        Sdf::Plane { normal, offset }
+    }
+
+    pub fn mandelbulb(power: f32, iterations: u32) -> Sdf {
+        Sdf::Mandelbulb { power, iterations }
+    }
+
+    pub fn translate(self, offset: Vec3) -> Sdf {
+        Sdf::Translate(offset, Box::new(self))
+    }
+
+    pub fn scale(self, factor: f32) -> Sdf {
+        Sdf::Scale(factor, Box::new(self))
+    }
+
+    pub fn union(self, other: Sdf) -> Sdf {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn intersection(self, other: Sdf) -> Sdf {
+        Sdf::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn difference(self, other: Sdf) -> Sdf {
+        Sdf::Difference(Box::new(self), Box::new(other))
+    }
+
+    pub fn smooth_union(self, other: Sdf, smoothness: f32) -> Sdf {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), smoothness)
+    }
+
+    pub fn repeat(self, period: Vec3) -> Sdf {
+        Sdf::Repeat(period, Box::new(self))
+    }
+
+    pub fn twist(self, rate: f32) -> Sdf {
+        Sdf::Twist(rate, Box::new(self))
+    }
+
+    pub fn displace(self, amplitude: f32, frequency: f32) -> Sdf {
+        Sdf::Displace(amplitude, frequency, Box::new(self))
+    }
+
+    pub fn distance(&self, point: &Vec3) -> f32 {
+        match self {
+            Sdf::Sphere { radius } => point.length() - radius,
+            Sdf::Box { half_size } => box_distance(point, half_size),
+            Sdf::RoundBox { half_size, radius } => {
+                let inner = half_size - Vec3::new(*radius, *radius, *radius);
+                box_distance(point, &inner) - radius
+            }
+            Sdf::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let radial = (point.x * point.x + point.z * point.z).sqrt() - major_radius;
+                (radial * radial + point.y * point.y).sqrt() - minor_radius
+            }
+            Sdf::Capsule { start, end, radius } => {
+                let to_point = point - start;
+                let segment = end - start;
+                let factor = (Vec3::dot_product(&to_point, &segment)
+                    / Vec3::dot_product(&segment, &segment))
+                .clamp(0.0, 1.0);
+
+                (to_point - segment * factor).length() - radius
+            }
+            Sdf::Plane { normal, offset } => Vec3::dot_product(point, normal) - offset,
+            Sdf::Mandelbulb { power, iterations } => {
+                mandelbulb_distance(point, *power, *iterations)
+            }
+            Sdf::Translate(offset, sdf) => sdf.distance(&(point - offset)),
+            Sdf::Scale(factor, sdf) => sdf.distance(&(point * (1.0 / factor))) * factor,
+            Sdf::Union(left, right) => left.distance(point).min(right.distance(point)),
+            Sdf::Intersection(left, right) => left.distance(point).max(right.distance(point)),
+            Sdf::Difference(left, right) => left.distance(point).max(-right.distance(point)),
+            Sdf::SmoothUnion(left, right, smoothness) => {
+                let left = left.distance(point);
+                let right = right.distance(point);
+
+                // Polynomial smooth minimum
+                let h = (0.5 + 0.5 * (right - left) / smoothness).clamp(0.0, 1.0);
+                right + (left - right) * h - smoothness * h * (1.0 - h)
+            }
+            Sdf::Repeat(period, sdf) => sdf.distance(&Vec3::new(
+                repeat(point.x, period.x),
+                repeat(point.y, period.y),
+                repeat(point.z, period.z),
+            )),
+            Sdf::Twist(rate, sdf) => {
+                let (sin, cos) = (rate * point.y).sin_cos();
+                sdf.distance(&Vec3::new(
+                    cos * point.x - sin * point.z,
+                    point.y,
+                    sin * point.x + cos * point.z,
+                ))
+            }
+            Sdf::Displace(amplitude, frequency, sdf) => {
+                sdf.distance(point)
+                    + amplitude
+                        * (frequency * point.x).sin()
+                        * (frequency * point.y).sin()
+                        * (frequency * point.z).sin()
+            }
+        }
+    }
+
+    // Surface normal, from the gradient of the distance (central differences)
+    pub fn normal(&self, point: &Vec3) -> Vec3 {
+        let epsilon = 1e-4;
+        let dx = Vec3::new(epsilon, 0.0, 0.0);
+        let dy = Vec3::new(0.0, epsilon, 0.0);
+        let dz = Vec3::new(0.0, 0.0, epsilon);
+
+        let mut normal = Vec3::new(
+            self.distance(&(point + dx)) - self.distance(&(point - dx)),
+            self.distance(&(point + dy)) - self.distance(&(point - dy)),
+            self.distance(&(point + dz)) - self.distance(&(point - dz)),
+        );
+        normal.normalize();
+
+        normal
+    }
+}
+
+// Geometry rendered by sphere tracing a distance function
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdfGeometry {
+    bounds: Option<BoundingBox>,
+    color: Color,
+    epsilon: f32,
+    max_distance: f32,
+    max_steps: u32,
+    reflection_factor: Option<f32>,
+    sdf: Sdf,
+    step_factor: f32,
+    transparency_factor: Option<f32>,
+}
+
+impl SdfGeometry {
+    pub fn new(
+        sdf: Sdf,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> SdfGeometry {
+        SdfGeometry {
+            bounds: None,
+            color,
+            epsilon: 1e-4,
+            max_distance: 100.0,
+            max_steps: 256,
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            sdf,
+            step_factor: 1.0,
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+        }
+    }
+
+    // Only marches inside the box, and gives the geometry finite bounds
+    pub fn with_bounds(self, bounds: BoundingBox) -> SdfGeometry {
+        SdfGeometry {
+            bounds: Some(bounds),
+            ..self
+        }
+    }
+
+    // Twist and displacement don't give exact distances, which have to be scaled down to avoid
+    // stepping through the surface
+    pub fn with_step_factor(self, step_factor: f32) -> SdfGeometry {
+        SdfGeometry {
+            step_factor,
+            ..self
+        }
+    }
+
+    pub fn with_precision(self, epsilon: f32, max_steps: u32, max_distance: f32) -> SdfGeometry {
+        SdfGeometry {
+            epsilon,
+            max_steps,
+            max_distance,
+            ..self
+        }
+    }
+
+    pub fn get_sdf(&self) -> &Sdf {
+        &self.sdf
+    }
+
+    // Marches from `start` while the sign of the distance is `sign`, until the surface is reached
+    fn march(&self, ray: &Ray, start: f32, end: f32, sign: f32) -> Option<f32> {
+        let mut distance = start;
+        for _ in 0..self.max_steps {
+            let step = sign * self.sdf.distance(&ray.point_at(distance));
+            if step < self.epsilon {
+                return Some(distance);
+            }
+
+            distance += step * self.step_factor;
+            if distance > end {
+                break;
+            }
+        }
+
+        None
+    }
+}
+
+impl Geometry for SdfGeometry {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let (start, end) = match &self.bounds {
+            Some(bounds) => {
+                let (enter, exit) = bounds.intersect(ray)?;
+                (enter.max(0.0), exit.min(self.max_distance))
+            }
+            None => (0.0, self.max_distance),
+        };
+
+        // Like spheres, rays starting inside the shape don't hit it
+        if self.sdf.distance(ray.get_origin()) < 0.0 {
+            return None;
+        }
+
+        let distance = self.march(ray, start, end, 1.0)?;
+
+        if let Some(exit_dist) = exit_dist {
+            // Only asked for by transparent objects, marching on through the inside of the shape
+            let inside_start = distance + 2.0 * self.epsilon;
+            *exit_dist = self
+                .march(ray, inside_start, end, -1.0)
+                .unwrap_or(inside_start);
+        }
+
+        if let Some(hit_info) = hitinfo {
+            hit_info.position = ray.point_at(distance);
+            hit_info.set_normal_only(self.sdf.normal(&hit_info.position));
+        }
+
+        Some(distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.color
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mandelbulb_distance_is_defined_at_the_origin() {
+        let mandelbulb = Sdf::mandelbulb(8.0, 12);
+
+        assert_eq!(mandelbulb.distance(&Vec3::zero()), 0.0);
+        for point in [
+            Vec3::new(0.0, 0.0, 1e-20),
+            Vec3::new(0.0, 0.0, -0.3),
+            Vec3::new(0.2, -0.1, 0.05),
+            Vec3::new(0.0, 0.0, 1.5),
+        ] {
+            assert!(mandelbulb.distance(&point).is_finite(), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn exit_distance_is_marched_on_request() {
+        let sphere = SdfGeometry::new(Sdf::sphere(1.0), Color::new(1.0, 1.0, 1.0), 0.0, 0.0);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+        let mut exit_dist = 0.0;
+        let distance = sphere
+            .compute_hit(&ray, None, Some(&mut exit_dist))
+            .unwrap();
+
+        assert!((distance - 4.0).abs() < 1e-3);
+        assert!((exit_dist - 6.0).abs() < 1e-3);
+        assert_eq!(sphere.compute_hit(&ray, None, None), Some(distance));
+    }
+}
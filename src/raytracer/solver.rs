@@ -0,0 +1,259 @@
+// Real roots of polynomials, sorted in ascending order.
+// Computations are done in double precision as the quartic case is badly conditioned.
+
+const EPSILON: f64 = 1e-12;
+
+// a x^2 + b x + c = 0
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < EPSILON {
+        if b.abs() < EPSILON {
+            return Vec::new();
+        }
+
+        return vec![-c / b];
+    }
+
+    let delta = b * b - 4.0 * a * c;
+    if delta < 0.0 {
+        return Vec::new();
+    }
+
+    if delta == 0.0 {
+        return vec![-b / (2.0 * a)];
+    }
+
+    // Avoids the cancellation of -b + sqrt(delta) when b is large
+    let q = -0.5 * (b + b.signum() * delta.sqrt());
+    let mut roots = vec![q / a, c / q];
+    roots.sort_by(|a, b| a.total_cmp(b));
+
+    roots
+}
+
+// a x^3 + b x^2 + c x + d = 0
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a.abs() < EPSILON {
+        return solve_quadratic(b, c, d);
+    }
+
+    let (b, c, d) = (b / a, c / a, d / a);
+
+    // Depressed cubic t^3 + p t + q with x = t - b / 3
+    let shift = b / 3.0;
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+
+    let mut roots = if p.abs() < EPSILON {
+        vec![(-q).cbrt()]
+    } else {
+        let delta = q * q / 4.0 + p * p * p / 27.0;
+        if delta > 0.0 {
+            // Single real root (Cardano)
+            let sqrt_delta = delta.sqrt();
+            vec![(-q / 2.0 + sqrt_delta).cbrt() + (-q / 2.0 - sqrt_delta).cbrt()]
+        } else {
+            // Three real roots (trigonometric method)
+            let m = 2.0 * (-p / 3.0).sqrt();
+            let theta = (3.0 * q / (p * m)).clamp(-1.0, 1.0).acos() / 3.0;
+            (0..3)
+                .map(|k| m * (theta - 2.0 * std::f64::consts::PI * k as f64 / 3.0).cos())
+                .collect()
+        }
+    };
+
+    for root in roots.iter_mut() {
+        *root -= shift;
+    }
+
+    roots.sort_by(|a, b| a.total_cmp(b));
+    roots
+}
+
+// a x^4 + b x^3 + c x^2 + d x + e = 0, using Ferrari's method
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a.abs() < EPSILON {
+        return solve_cubic(b, c, d, e);
+    }
+
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Depressed quartic y^4 + p y^2 + q y + r with x = y - b / 4
+    let shift = b / 4.0;
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < EPSILON {
+        // Biquadratic
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                let y = z.sqrt();
+                roots.push(y);
+                roots.push(-y);
+            }
+        }
+    } else {
+        // Factorization into (y^2 + s y + alpha)(y^2 - s y + beta), z = s^2 being a positive root
+        // of the resolvent cubic
+        let z = solve_cubic(1.0, 2.0 * p, p * p - 4.0 * r, -q * q)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        if z > 0.0 {
+            let s = z.sqrt();
+            let alpha = (p + z - q / s) / 2.0;
+            let beta = (p + z + q / s) / 2.0;
+
+            roots.extend(solve_quadratic(1.0, s, alpha));
+            roots.extend(solve_quadratic(1.0, -s, beta));
+        }
+    }
+
+    for root in roots.iter_mut() {
+        // Newton iterations to get back the precision lost through the resolvent
+        let mut x = *root - shift;
+        for _ in 0..2 {
+            let value = (((x + b) * x + c) * x + d) * x + e;
+            let derivative = ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+            if derivative.abs() < EPSILON {
+                break;
+            }
+
+            x -= value / derivative;
+        }
+
+        *root = x;
+    }
+
+    roots.sort_by(|a, b| a.total_cmp(b));
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Coefficients of the monic polynomial with the given roots, highest degree first
+    fn expand(roots: &[f64]) -> Vec<f64> {
+        let mut coefficients = vec![1.0];
+        for root in roots {
+            let mut next = coefficients.clone();
+            next.push(0.0);
+            for (i, coefficient) in coefficients.iter().enumerate() {
+                next[i + 1] -= root * coefficient;
+            }
+            coefficients = next;
+        }
+
+        coefficients
+    }
+
+    fn quartic(roots: &[f64]) -> Vec<f64> {
+        let c = expand(roots);
+        solve_quartic(c[0], c[1], c[2], c[3], c[4])
+    }
+
+    fn assert_roots(roots: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (root, expected_root) in roots.iter().zip(expected) {
+            assert!(
+                (root - expected_root).abs() < tolerance,
+                "{:?} != {:?}",
+                roots,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn quadratic_and_cubic_roots() {
+        assert_roots(&solve_quadratic(1.0, -1.0, -6.0), &[-2.0, 3.0], 1e-12);
+        assert_roots(&solve_quadratic(0.0, 2.0, -4.0), &[2.0], 1e-12);
+        assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+
+        let c = expand(&[-2.0, 0.5, 4.0]);
+        assert_roots(
+            &solve_cubic(c[0], c[1], c[2], c[3]),
+            &[-2.0, 0.5, 4.0],
+            1e-9,
+        );
+        assert_roots(&solve_cubic(1.0, 0.0, 0.0, -8.0), &[2.0], 1e-12);
+    }
+
+    #[test]
+    fn quartic_known_roots() {
+        assert_roots(
+            &quartic(&[2.0, -3.0, 0.5, -1.0]),
+            &[-3.0, -1.0, 0.5, 2.0],
+            1e-9,
+        );
+        assert_roots(
+            &quartic(&[-0.25, 0.25, 10.0, 12.5]),
+            &[-0.25, 0.25, 10.0, 12.5],
+            1e-9,
+        );
+
+        // Scaled and with a null leading coefficient
+        let c = expand(&[1.0, 2.0, 3.0, 4.0]);
+        let scaled: Vec<f64> = c.iter().map(|coefficient| coefficient * -2.5).collect();
+        assert_roots(
+            &solve_quartic(scaled[0], scaled[1], scaled[2], scaled[3], scaled[4]),
+            &[1.0, 2.0, 3.0, 4.0],
+            1e-9,
+        );
+        assert_roots(
+            &solve_quartic(0.0, 1.0, -6.0, 11.0, -6.0),
+            &[1.0, 2.0, 3.0],
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn quartic_double_roots() {
+        // A double root may come out once or twice
+        let roots = quartic(&[1.0, 1.0, -2.0, 3.0]);
+        assert!(roots.len() >= 3, "{:?}", roots);
+        assert!((roots[0] + 2.0).abs() < 1e-6);
+        assert!((roots[roots.len() - 1] - 3.0).abs() < 1e-6);
+        for root in &roots[1..roots.len() - 1] {
+            assert!((root - 1.0).abs() < 1e-6, "{:?}", roots);
+        }
+
+        // Biquadratic, both roots being double
+        let roots = quartic(&[1.0, 1.0, -1.0, -1.0]);
+        assert!(roots.len() >= 2, "{:?}", roots);
+        assert!(
+            roots.iter().all(|root| (root.abs() - 1.0).abs() < 1e-6),
+            "{:?}",
+            roots
+        );
+    }
+
+    #[test]
+    fn quartic_complex_roots() {
+        // (x^2 + 1)(x^2 + 4)
+        assert!(solve_quartic(1.0, 0.0, 5.0, 0.0, 4.0).is_empty());
+        // (x^2 + 2x + 5)(x^2 - x + 1)
+        let (a, b) = ([1.0, 2.0, 5.0], [1.0, -1.0, 1.0]);
+        let product = [
+            a[0] * b[0],
+            a[0] * b[1] + a[1] * b[0],
+            a[0] * b[2] + a[1] * b[1] + a[2] * b[0],
+            a[1] * b[2] + a[2] * b[1],
+            a[2] * b[2],
+        ];
+        assert!(
+            solve_quartic(product[0], product[1], product[2], product[3], product[4]).is_empty()
+        );
+
+        // (x - 1)(x - 2)(x^2 + 1), two real roots as for a ray going through one side of a torus
+        let c = expand(&[1.0, 2.0]);
+        assert_roots(
+            &solve_quartic(1.0, c[1], c[2] + 1.0, c[1], c[2]),
+            &[1.0, 2.0],
+            1e-9,
+        );
+    }
+}
@@ -1,13 +1,16 @@
+use super::bounds::BoundingBox;
 use super::color::Color;
-use super::geometry::Geometry;
-use super::geometry::HitInfo;
+use super::geometry::{Crossing, Geometry, HitInfo, Interval};
+use super::motion::Motion;
 use super::ray::Ray;
 use super::vec3::Vec3;
+use std::f32::consts::PI;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Sphere {
     center: Vec3,
     color: Color,
+    motion: Motion,
     radius: f32,
     reflection_factor: Option<f32>,
     transparency_factor: Option<f32>,
@@ -25,6 +28,7 @@ impl Sphere {
             center,
             radius,
             color,
+            motion: Motion::Static,
             reflection_factor: if reflection_factor > 0.001 {
                 Some(reflection_factor)
             } else {
@@ -38,10 +42,18 @@ impl Sphere {
         }
     }
 
+    pub fn with_motion(self, motion: Motion) -> Sphere {
+        Sphere { motion, ..self }
+    }
+
     pub fn get_center(&self) -> Vec3 {
         self.center
     }
 
+    pub fn get_center_at(&self, time: f32) -> Vec3 {
+        self.center + self.motion.get_offset(time)
+    }
+
     pub fn get_color(&self) -> Color {
         self.color
     }
@@ -49,27 +61,18 @@ impl Sphere {
     pub fn get_radius(&self) -> f32 {
         self.radius
     }
-}
 
-impl Geometry for Sphere {
-    fn compute_hit(
-        &self,
-        ray: &Ray,
-        hitinfo: Option<&mut HitInfo>,
-        exit_dist: Option<&mut f32>,
-    ) -> Option<f32> {
-        let ray_to_sphere = ray.get_origin() - self.center;
+    // Center at the ray time, with the distances where the ray enters and leaves the sphere
+    fn intersect(&self, ray: &Ray) -> Option<(Vec3, f32, f32)> {
+        let center = self.get_center_at(ray.get_time());
+
+        let ray_to_sphere = ray.get_origin() - center;
         let a = Vec3::dot_product(ray.get_direction(), ray.get_direction());
         let b = Vec3::dot_product(ray.get_direction(), &ray_to_sphere);
         let c = Vec3::dot_product(&ray_to_sphere, &ray_to_sphere) - self.radius * self.radius;
 
         let delta = (b * b) - a * c;
 
-        let compute_result = |param: f32, hit_info: &mut HitInfo| {
-            hit_info.position = ray.point_at(param);
-            hit_info.normal = hit_info.position - self.center;
-        };
-
         if delta >= 0.0 {
             let sqr_delta = delta.sqrt();
             let mut enter_distance = (-b - sqr_delta) / a;
@@ -79,31 +82,100 @@ impl Geometry for Sphere {
                 std::mem::swap(&mut enter_distance, &mut exit_distance);
             }
 
-            if enter_distance >= 0.0 {
-                if let Some(exit_dist) = exit_dist {
-                    *exit_dist = exit_distance
-                }
+            return Some((center, enter_distance, exit_distance));
+        }
+
+        None
+    }
+
+    fn get_hit_info(&self, center: &Vec3, position: Vec3) -> HitInfo {
+        let mut hit_info = HitInfo::new();
+        hit_info.position = position;
+        hit_info.normal = position - center;
+        hit_info.geometric_normal = hit_info.normal;
+
+        // Latitude-longitude coordinates, v going up from the bottom pole
+        let local = position - center;
+        let radial = (local.x * local.x + local.z * local.z).sqrt().max(1e-6);
+        hit_info.uv = (
+            local.z.atan2(local.x) / (2.0 * PI) + 0.5,
+            1.0 - (local.y / self.radius).clamp(-1.0, 1.0).acos() / PI,
+        );
+        hit_info.dpdu = 2.0 * PI * Vec3::new(-local.z, 0.0, local.x);
+        hit_info.dpdv = PI
+            * Vec3::new(
+                -local.x * local.y / radial,
+                radial,
+                -local.z * local.y / radial,
+            );
+
+        hit_info
+    }
+}
+
+impl Geometry for Sphere {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let (center, enter_distance, exit_distance) = self.intersect(ray)?;
 
-                if let Some(hit_info) = hitinfo {
-                    compute_result(enter_distance, hit_info)
-                }
+        if enter_distance >= 0.0 {
+            if let Some(exit_dist) = exit_dist {
+                *exit_dist = exit_distance
+            }
 
-                return Some(enter_distance);
+            if let Some(hit_info) = hitinfo {
+                *hit_info = self.get_hit_info(&center, ray.point_at(enter_distance));
             }
+
+            return Some(enter_distance);
         }
 
         None
     }
 
-    fn get_color(&self, position: &Vec3) -> Color {
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
         self.get_color()
     }
 
-    fn get_reflection_factor(&self) -> Option<f32> {
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
         self.reflection_factor
     }
 
-    fn get_transparency_factor(&self) -> Option<f32> {
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
         self.transparency_factor
     }
+
+    // Covers the whole motion of the sphere
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        let centers: Vec<Vec3> = self
+            .motion
+            .get_key_offsets()
+            .iter()
+            .map(|offset| self.center + offset)
+            .collect();
+
+        let bounds = BoundingBox::from_points(&centers);
+        Some(BoundingBox::new(bounds.min - radius, bounds.max + radius))
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        match self.intersect(ray) {
+            Some((center, enter_distance, exit_distance)) => vec![Interval {
+                enter: Crossing {
+                    distance: enter_distance,
+                    hit_info: self.get_hit_info(&center, ray.point_at(enter_distance)),
+                },
+                exit: Crossing {
+                    distance: exit_distance,
+                    hit_info: self.get_hit_info(&center, ray.point_at(exit_distance)),
+                },
+            }],
+            None => Vec::new(),
+        }
+    }
 }
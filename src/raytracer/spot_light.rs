@@ -85,7 +85,7 @@ impl SpotLight {
 }
 
 fn reflect(r: &Vec3, n: &Vec3) -> Vec3 {
-    2.0 * n * Vec3::dot_product(&n, &r) - r
+    2.0 * n * Vec3::dot_product(n, r) - r
 }
 
 impl Light for SpotLight {
@@ -95,7 +95,7 @@ impl Light for SpotLight {
 
         direction.normalize_out_length(&mut length);
 
-        let diffuse_factor = Vec3::dot_product(&direction, &hit_info.normal).max(0.0);
+        let diffuse_factor = hit_info.get_diffuse_factor(&direction);
 
         let light_color = self.get_color();
 
@@ -121,7 +121,7 @@ impl Light for SpotLight {
         pixel_color.b += specular_factor;
 
         if scene.intersect_dist(
-            Ray::new(hit_info.position + direction * 0.01, direction),
+            Ray::new(hit_info.position + direction * 0.01, direction).with_time(ray.get_time()),
             length,
             0.0,
         ) {
@@ -130,4 +130,34 @@ impl Light for SpotLight {
             pixel_color.b *= 0.1;
         }
     }
+
+    fn get_incident_light(
+        &self,
+        scene: &Scene,
+        position: &Vec3,
+        time: f32,
+    ) -> Option<(Vec3, Color)> {
+        let mut direction = self.get_position() - position;
+        let mut length = 0.0;
+
+        direction.normalize_out_length(&mut length);
+
+        let mut att = (self.get_attenuation() - self.get_inv_radius() * length).max(0.0);
+
+        let curr_angle = Vec3::dot_product(self.get_direction(), &(-direction));
+        let inner_minus_outer = self.get_inner_angle_cosinus() - self.get_outer_angle_cosinus();
+        att *= ((curr_angle - self.get_outer_angle_cosinus()) / inner_minus_outer).clamp(0.0, 1.0);
+
+        if att <= 0.0
+            || scene.intersect_dist(
+                Ray::new(position + direction * 0.01, direction).with_time(time),
+                length,
+                0.0,
+            )
+        {
+            return None;
+        }
+
+        Some((direction, self.get_color() * att))
+    }
 }
@@ -0,0 +1,183 @@
+use super::camera::{camera_basis, Camera};
+use super::equirectangular_camera::EquirectangularCamera;
+use super::perspective_camera::PerspectiveCamera;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StereoLayout {
+    // Left eye on the left half of the image, right eye on the right half
+    SideBySide,
+    // Left eye on the top half of the image, right eye on the bottom half
+    TopBottom,
+}
+
+// Renders both eyes in a single image, the eye cameras being built for the size of one half
+pub struct StereoCamera {
+    layout: StereoLayout,
+    left: Box<dyn Camera>,
+    right: Box<dyn Camera>,
+}
+
+impl StereoCamera {
+    pub fn new(
+        left: Box<dyn Camera>,
+        right: Box<dyn Camera>,
+        layout: StereoLayout,
+    ) -> StereoCamera {
+        StereoCamera {
+            layout,
+            left,
+            right,
+        }
+    }
+
+    // Off-axis perspective rig, objects at convergence distance appear at screen depth
+    #[allow(clippy::too_many_arguments)]
+    pub fn perspective(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        eye_aspect: f32,
+        fovy: f32,
+        aperture: f32,
+        interocular_distance: f32,
+        convergence_distance: f32,
+        layout: StereoLayout,
+    ) -> StereoCamera {
+        let center = PerspectiveCamera::new(
+            position,
+            lookat,
+            up,
+            eye_aspect,
+            fovy,
+            aperture,
+            convergence_distance,
+        );
+
+        let (u, _, _) = camera_basis(&position, &lookat, &up);
+        let eye_offset = interocular_distance / 2.0 * u;
+
+        StereoCamera::new(
+            Box::new(center.with_eye_offset(-eye_offset)),
+            Box::new(center.with_eye_offset(eye_offset)),
+            layout,
+        )
+    }
+
+    // Omni-directional stereo for 360 panoramas
+    pub fn omnidirectional(
+        position: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        interocular_distance: f32,
+        convergence_distance: f32,
+        layout: StereoLayout,
+    ) -> StereoCamera {
+        let half_distance = interocular_distance / 2.0;
+
+        StereoCamera::new(
+            Box::new(EquirectangularCamera::new_stereo_eye(
+                position,
+                lookat,
+                up,
+                -half_distance,
+                convergence_distance,
+            )),
+            Box::new(EquirectangularCamera::new_stereo_eye(
+                position,
+                lookat,
+                up,
+                half_distance,
+                convergence_distance,
+            )),
+            layout,
+        )
+    }
+
+    // Camera of the eye seeing a position on the image, with the position on the image of that eye
+    fn get_eye(&self, x: f32, y: f32) -> (&dyn Camera, f32, f32) {
+        match self.layout {
+            StereoLayout::SideBySide => {
+                if x < 0.5 {
+                    (self.left.as_ref(), x * 2.0, y)
+                } else {
+                    (self.right.as_ref(), x * 2.0 - 1.0, y)
+                }
+            }
+            StereoLayout::TopBottom => {
+                if y < 0.5 {
+                    (self.left.as_ref(), x, y * 2.0)
+                } else {
+                    (self.right.as_ref(), x, y * 2.0 - 1.0)
+                }
+            }
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn get_exposure(&self) -> f32 {
+        self.left.get_exposure()
+    }
+
+    fn get_ray(&self, rng: &mut rand::XorShiftRng, x: f32, y: f32) -> Option<Ray> {
+        let (eye, x, y) = self.get_eye(x, y);
+        eye.get_ray(rng, x, y)
+    }
+
+    // The neighbor ray is taken from the same eye, even for the last pixels of an eye
+    fn get_spread(
+        &self,
+        rng: &rand::XorShiftRng,
+        ray: &Ray,
+        x: f32,
+        y: f32,
+        pixel_width: f32,
+    ) -> f32 {
+        let (eye, x, y) = self.get_eye(x, y);
+        let pixel_width = match self.layout {
+            StereoLayout::SideBySide => pixel_width * 2.0,
+            StereoLayout::TopBottom => pixel_width,
+        };
+
+        eye.get_spread(rng, ray, x, y, pixel_width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn spread_stays_in_the_eye() {
+        let pixel_width = 1.0 / 200.0;
+        let rng = rand::XorShiftRng::from_seed([1, 2, 3, 4]);
+
+        for &layout in &[StereoLayout::SideBySide, StereoLayout::TopBottom] {
+            let camera = StereoCamera::perspective(
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, -1.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                1.0,
+                90.0,
+                0.0,
+                0.065,
+                2.0,
+                layout,
+            );
+
+            let spread = |x: f32| {
+                let ray = camera.get_ray(&mut rng.clone(), x, 0.25).unwrap();
+                camera.get_spread(&rng, &ray, x, 0.25, pixel_width)
+            };
+
+            // The last pixel of the left eye, next to the first one of the right eye
+            let seam = spread(0.5 - pixel_width);
+            let center = spread(0.25);
+
+            assert!(seam > 0.0 && seam < center * 1.5, "{} {}", seam, center);
+        }
+    }
+}
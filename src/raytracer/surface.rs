@@ -0,0 +1,68 @@
+use super::bounds::BoundingBox;
+use super::bsdf::{Bsdf, Material};
+use super::color::Color;
+use super::geometry::{Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+// Geometry made of a material, shaded by its BSDF instead of its color and factors
+#[derive(Clone)]
+pub struct Surface {
+    geometry: Arc<dyn Geometry>,
+    material: Arc<dyn Material>,
+}
+
+impl Surface {
+    pub fn new(geometry: Arc<dyn Geometry>, material: Arc<dyn Material>) -> Surface {
+        Surface { geometry, material }
+    }
+
+    pub fn get_geometry(&self) -> &Arc<dyn Geometry> {
+        &self.geometry
+    }
+
+    pub fn get_material(&self) -> &Arc<dyn Material> {
+        &self.material
+    }
+}
+
+impl Geometry for Surface {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        self.geometry.compute_hit(ray, hitinfo, exit_dist)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.geometry.get_color(hit_info)
+    }
+
+    fn get_shading_normal(&self, hit_info: &HitInfo) -> Vec3 {
+        self.geometry.get_shading_normal(hit_info)
+    }
+
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Option<Box<dyn Bsdf>> {
+        Some(self.material.get_bsdf(hit_info))
+    }
+
+    // Reflection and transparency are up to the material
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        None
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        None
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.geometry.get_bounds()
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        self.geometry.get_intervals(ray)
+    }
+}
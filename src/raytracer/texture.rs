@@ -0,0 +1,457 @@
+use super::color::Color;
+use super::geometry::HitInfo;
+use super::image::Image;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+// Color varying over a surface
+pub trait Texture: Sync + Send {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color;
+
+    // For textures driving a single value, such as a factor
+    fn evaluate_scalar(&self, hit_info: &HitInfo) -> f32 {
+        let color = self.evaluate(hit_info);
+        (color.r + color.g + color.b) / 3.0
+    }
+}
+
+// Coordinates a pattern is laid out with
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureMapping {
+    Uv,
+    // x and z of the position (in object space for instances), for patterns projected from above
+    Planar,
+}
+
+impl TextureMapping {
+    pub fn get_coordinates(&self, hit_info: &HitInfo) -> (f32, f32) {
+        match self {
+            TextureMapping::Uv => hit_info.uv,
+            TextureMapping::Planar => (hit_info.position.x, hit_info.position.z),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ConstantTexture {
+    color: Color,
+}
+
+impl ConstantTexture {
+    pub fn new(color: Color) -> ConstantTexture {
+        ConstantTexture { color }
+    }
+
+    // Grey, for factors
+    pub fn from_value(value: f32) -> ConstantTexture {
+        ConstantTexture {
+            color: Color::new(value, value, value),
+        }
+    }
+}
+
+impl Texture for ConstantTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        self.color
+    }
+}
+
+// Squares of `size` alternating between two colors
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CheckerTexture {
+    first: Color,
+    mapping: TextureMapping,
+    // Rows mirrored across v = 0, so the squares on both sides of it are doubled
+    mirrored_rows: bool,
+    second: Color,
+    size: f32,
+}
+
+impl CheckerTexture {
+    pub fn new(first: Color, second: Color, size: f32, mapping: TextureMapping) -> CheckerTexture {
+        CheckerTexture {
+            first,
+            mapping,
+            mirrored_rows: false,
+            second,
+            size,
+        }
+    }
+
+    // The pattern of the original ground, which took the remainder of v without wrapping negative values
+    pub fn with_mirrored_rows(self) -> CheckerTexture {
+        CheckerTexture {
+            mirrored_rows: true,
+            ..self
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        let (u, v) = self.mapping.get_coordinates(hit_info);
+
+        let size = self.size;
+
+        let row = if self.mirrored_rows {
+            (v % size).abs()
+        } else {
+            v.rem_euclid(size)
+        };
+        let is_even = row > size / 2.0;
+
+        if (u.rem_euclid(size) > size / 2.0) ^ is_even {
+            self.first
+        } else {
+            self.second
+        }
+    }
+}
+
+// How images are read between their pixels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    // Bilinear, blended between the two mipmap levels closest to the footprint of the hit
+    Trilinear,
+}
+
+// How images are extended outside of the 0 to 1 uv range
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureWrap {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl TextureWrap {
+    // Pixel actually read for a coordinate possibly outside of the image
+    pub fn apply(&self, coordinate: i64, size: usize) -> usize {
+        let size = size as i64;
+        let coordinate = match self {
+            TextureWrap::Repeat => coordinate.rem_euclid(size),
+            TextureWrap::Clamp => coordinate.clamp(0, size - 1),
+            TextureWrap::Mirror => {
+                let coordinate = coordinate.rem_euclid(2 * size);
+                if coordinate < size {
+                    coordinate
+                } else {
+                    2 * size - 1 - coordinate
+                }
+            }
+        };
+
+        coordinate as usize
+    }
+}
+
+// How the values of an image file are stored
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ColorSpace {
+    Linear,
+    // Gamma encoded, as for photographs and painted color maps
+    Srgb,
+}
+
+// Image stretched over the 0 to 1 uv range.
+// Mipmap levels are shared between clones, so they are cheap to make with other settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTexture {
+    filter: TextureFilter,
+    levels: Arc<Vec<Image>>,
+    wrap: TextureWrap,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> ImageTexture {
+        let mut levels = vec![image];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.get_width() == 1 && last.get_height() == 1 {
+                break;
+            }
+
+            let level = last.downsample();
+            levels.push(level);
+        }
+
+        ImageTexture {
+            filter: TextureFilter::Bilinear,
+            levels: Arc::new(levels),
+            wrap: TextureWrap::Repeat,
+        }
+    }
+
+    pub fn load_png(path: &Path, color_space: ColorSpace) -> io::Result<ImageTexture> {
+        let image = Image::load_png(path)?;
+
+        Ok(ImageTexture::new(match color_space {
+            ColorSpace::Linear => image,
+            ColorSpace::Srgb => image.decode_srgb(),
+        }))
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> ImageTexture {
+        ImageTexture { filter, ..self }
+    }
+
+    pub fn with_wrap(self, wrap: TextureWrap) -> ImageTexture {
+        ImageTexture { wrap, ..self }
+    }
+
+    pub fn get_image(&self) -> &Image {
+        &self.levels[0]
+    }
+
+    pub fn get_level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn get_filter(&self) -> TextureFilter {
+        self.filter
+    }
+
+    pub fn get_wrap(&self) -> TextureWrap {
+        self.wrap
+    }
+
+    fn get_texel(&self, level: usize, x: i64, y: i64) -> Color {
+        let image = &self.levels[level];
+        image.get_pixel(
+            self.wrap.apply(x, image.get_width()),
+            self.wrap.apply(y, image.get_height()),
+        )
+    }
+
+    // v goes up while image rows go down
+    fn sample_nearest(&self, level: usize, u: f32, v: f32) -> Color {
+        let image = &self.levels[level];
+        let x = (u * image.get_width() as f32).floor() as i64;
+        let y = ((1.0 - v) * image.get_height() as f32).floor() as i64;
+
+        self.get_texel(level, x, y)
+    }
+
+    fn sample_bilinear(&self, level: usize, u: f32, v: f32) -> Color {
+        let image = &self.levels[level];
+
+        // Pixel centers are at half coordinates
+        let x = u * image.get_width() as f32 - 0.5;
+        let y = (1.0 - v) * image.get_height() as f32 - 0.5;
+        let (left, top) = (x.floor(), y.floor());
+        let (factor_x, factor_y) = (x - left, y - top);
+        let (left, top) = (left as i64, top as i64);
+
+        let lerp = |a: Color, b: Color, factor: f32| Color {
+            r: a.r + (b.r - a.r) * factor,
+            g: a.g + (b.g - a.g) * factor,
+            b: a.b + (b.b - a.b) * factor,
+        };
+
+        lerp(
+            lerp(
+                self.get_texel(level, left, top),
+                self.get_texel(level, left + 1, top),
+                factor_x,
+            ),
+            lerp(
+                self.get_texel(level, left, top + 1),
+                self.get_texel(level, left + 1, top + 1),
+                factor_x,
+            ),
+            factor_y,
+        )
+    }
+
+    // Mipmap level (possibly fractional) whose pixels are about as large as the footprint
+    fn get_level(&self, hit_info: &HitInfo) -> f32 {
+        let image = self.get_image();
+        let length_u = hit_info.dpdu.length();
+        let length_v = hit_info.dpdv.length();
+        if hit_info.footprint <= 0.0 || length_u <= 0.0 || length_v <= 0.0 {
+            return 0.0;
+        }
+
+        let pixels = (hit_info.footprint / length_u * image.get_width() as f32)
+            .max(hit_info.footprint / length_v * image.get_height() as f32);
+
+        pixels.log2().clamp(0.0, (self.levels.len() - 1) as f32)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        let (u, v) = hit_info.uv;
+
+        match self.filter {
+            TextureFilter::Nearest => self.sample_nearest(0, u, v),
+            TextureFilter::Bilinear => self.sample_bilinear(0, u, v),
+            TextureFilter::Trilinear => {
+                let level = self.get_level(hit_info);
+                let lower = level.floor() as usize;
+                let upper = (lower + 1).min(self.levels.len() - 1);
+                let factor = level - lower as f32;
+
+                let fine = self.sample_bilinear(lower, u, v);
+                let coarse = self.sample_bilinear(upper, u, v);
+
+                Color {
+                    r: fine.r + (coarse.r - fine.r) * factor,
+                    g: fine.g + (coarse.g - fine.g) * factor,
+                    b: fine.b + (coarse.b - fine.b) * factor,
+                }
+            }
+        }
+    }
+}
+
+// Texture computed by a function of the hit
+pub struct ProceduralTexture<F: Fn(&HitInfo) -> Color + Sync + Send> {
+    function: F,
+}
+
+impl<F: Fn(&HitInfo) -> Color + Sync + Send> ProceduralTexture<F> {
+    pub fn new(function: F) -> ProceduralTexture<F> {
+        ProceduralTexture { function }
+    }
+}
+
+impl<F: Fn(&HitInfo) -> Color + Sync + Send> Texture for ProceduralTexture<F> {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        (self.function)(hit_info)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+// One channel of a texture as grey, for images packing several factors such as glTF metallic-roughness maps
+#[derive(Clone)]
+pub struct ChannelTexture {
+    channel: Channel,
+    texture: Arc<dyn Texture>,
+}
+
+impl ChannelTexture {
+    pub fn new(texture: Arc<dyn Texture>, channel: Channel) -> ChannelTexture {
+        ChannelTexture { channel, texture }
+    }
+}
+
+impl Texture for ChannelTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        let value = self.evaluate_scalar(hit_info);
+        Color::new(value, value, value)
+    }
+
+    fn evaluate_scalar(&self, hit_info: &HitInfo) -> f32 {
+        let color = self.texture.evaluate(hit_info);
+
+        match self.channel {
+            Channel::Red => color.r,
+            Channel::Green => color.g,
+            Channel::Blue => color.b,
+        }
+    }
+}
+
+// Texture multiplied by a color, as factors applied to maps in material files
+#[derive(Clone)]
+pub struct ScaledTexture {
+    scale: Color,
+    texture: Arc<dyn Texture>,
+}
+
+impl ScaledTexture {
+    pub fn new(texture: Arc<dyn Texture>, scale: Color) -> ScaledTexture {
+        ScaledTexture { scale, texture }
+    }
+}
+
+impl Texture for ScaledTexture {
+    fn evaluate(&self, hit_info: &HitInfo) -> Color {
+        self.texture.evaluate(hit_info) * self.scale
+    }
+
+    fn evaluate_scalar(&self, hit_info: &HitInfo) -> f32 {
+        let scale = (self.scale.r + self.scale.g + self.scale.b) / 3.0;
+        self.texture.evaluate_scalar(hit_info) * scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracer::vec3::Vec3;
+
+    #[test]
+    fn wraps_read_pixels_inside_the_image() {
+        let read = |wrap: TextureWrap| -> Vec<usize> {
+            (-5..9)
+                .map(|coordinate| wrap.apply(coordinate, 4))
+                .collect()
+        };
+
+        assert_eq!(
+            read(TextureWrap::Repeat),
+            vec![3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0]
+        );
+        assert_eq!(
+            read(TextureWrap::Clamp),
+            vec![0, 0, 0, 0, 0, 0, 1, 2, 3, 3, 3, 3, 3, 3]
+        );
+        assert_eq!(
+            read(TextureWrap::Mirror),
+            vec![3, 3, 2, 1, 0, 0, 1, 2, 3, 3, 2, 1, 0, 0]
+        );
+    }
+
+    #[test]
+    fn level_follows_the_footprint() {
+        let texture = ImageTexture::new(Image::new(16, 8, vec![Color::black(); 128]));
+        assert_eq!(texture.get_level_count(), 5);
+
+        let mut hit_info = HitInfo::new();
+        hit_info.dpdu = Vec3::new(2.0, 0.0, 0.0);
+        hit_info.dpdv = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(texture.get_level(&hit_info), 0.0);
+
+        // 0.5 units are 4 pixels along u and v
+        hit_info.footprint = 0.5;
+        assert!((texture.get_level(&hit_info) - 2.0).abs() < 1e-6);
+
+        // The largest of both is kept
+        hit_info.dpdv = Vec3::new(0.0, 0.0, 0.25);
+        assert!((texture.get_level(&hit_info) - 4.0).abs() < 1e-6);
+
+        // Up to the last level
+        hit_info.footprint = 100.0;
+        assert_eq!(texture.get_level(&hit_info), 4.0);
+    }
+
+    #[test]
+    fn last_level_is_the_mean_color() {
+        let texture = ImageTexture::new(Image::new(
+            2,
+            2,
+            vec![
+                Color::new(1.0, 0.0, 0.0),
+                Color::new(0.0, 1.0, 0.0),
+                Color::new(0.0, 0.0, 1.0),
+                Color::new(1.0, 1.0, 1.0),
+            ],
+        ));
+
+        assert_eq!(texture.get_level_count(), 2);
+
+        let level = &texture.levels[1];
+        assert_eq!((level.get_width(), level.get_height()), (1, 1));
+        assert_eq!(level.get_pixel(0, 0), Color::new(0.5, 0.5, 0.5));
+    }
+}
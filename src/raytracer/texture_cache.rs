@@ -0,0 +1,45 @@
+use super::texture::{ColorSpace, ImageTexture};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Loads each image file once, objects using the same file sharing its pixels and mipmaps
+#[derive(Debug, Default)]
+pub struct TextureCache {
+    textures: Mutex<HashMap<(PathBuf, ColorSpace), ImageTexture>>,
+}
+
+impl TextureCache {
+    pub fn new() -> TextureCache {
+        TextureCache::default()
+    }
+
+    // The texture is returned with the default filter and wrap modes, which can be changed without reloading it
+    pub fn load_png(&self, path: &Path, color_space: ColorSpace) -> io::Result<ImageTexture> {
+        let key = (path.to_path_buf(), color_space);
+
+        if let Some(texture) = self.textures.lock().unwrap().get(&key) {
+            return Ok(texture.clone());
+        }
+
+        // Loaded without holding the lock, so other files can be loaded meanwhile
+        let texture = ImageTexture::load_png(path, color_space)?;
+
+        Ok(self
+            .textures
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert(texture)
+            .clone())
+    }
+
+    pub fn get_texture_count(&self) -> usize {
+        self.textures.lock().unwrap().len()
+    }
+
+    pub fn clear(&self) {
+        self.textures.lock().unwrap().clear();
+    }
+}
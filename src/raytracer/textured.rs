@@ -0,0 +1,106 @@
+use super::bounds::BoundingBox;
+use super::bsdf::Bsdf;
+use super::color::Color;
+use super::geometry::{Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+// Factor read from a texture, with the same threshold as the ones given to primitives
+fn texture_factor(texture: &Arc<dyn Texture>, hit_info: &HitInfo) -> Option<f32> {
+    let factor = texture.evaluate_scalar(hit_info);
+    if factor > 0.001 {
+        Some(factor)
+    } else {
+        None
+    }
+}
+
+// Paints any geometry with a texture instead of its own color, and optionally drives its reflection
+// and transparency factors with textures too
+#[derive(Clone)]
+pub struct Textured {
+    geometry: Arc<dyn Geometry>,
+    reflection: Option<Arc<dyn Texture>>,
+    texture: Arc<dyn Texture>,
+    transparency: Option<Arc<dyn Texture>>,
+}
+
+impl Textured {
+    pub fn new(geometry: Arc<dyn Geometry>, texture: Arc<dyn Texture>) -> Textured {
+        Textured {
+            geometry,
+            reflection: None,
+            texture,
+            transparency: None,
+        }
+    }
+
+    pub fn with_reflection(self, reflection: Arc<dyn Texture>) -> Textured {
+        Textured {
+            reflection: Some(reflection),
+            ..self
+        }
+    }
+
+    pub fn with_transparency(self, transparency: Arc<dyn Texture>) -> Textured {
+        Textured {
+            transparency: Some(transparency),
+            ..self
+        }
+    }
+
+    pub fn get_geometry(&self) -> &Arc<dyn Geometry> {
+        &self.geometry
+    }
+
+    pub fn get_texture(&self) -> &Arc<dyn Texture> {
+        &self.texture
+    }
+}
+
+impl Geometry for Textured {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        self.geometry.compute_hit(ray, hitinfo, exit_dist)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.texture.evaluate(hit_info)
+    }
+
+    fn get_shading_normal(&self, hit_info: &HitInfo) -> Vec3 {
+        self.geometry.get_shading_normal(hit_info)
+    }
+
+    fn get_bsdf(&self, hit_info: &HitInfo) -> Option<Box<dyn Bsdf>> {
+        self.geometry.get_bsdf(hit_info)
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        match &self.reflection {
+            Some(reflection) => texture_factor(reflection, hit_info),
+            None => self.geometry.get_reflection_factor(hit_info),
+        }
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        match &self.transparency {
+            Some(transparency) => texture_factor(transparency, hit_info),
+            None => self.geometry.get_transparency_factor(hit_info),
+        }
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.geometry.get_bounds()
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        self.geometry.get_intervals(ray)
+    }
+}
@@ -0,0 +1,83 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::{Geometry, HitInfo, Interval};
+use super::plane::Plane;
+use super::ray::Ray;
+use super::texture::{CheckerTexture, Texture, TextureMapping};
+use super::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TexturedPlane {
+    plane: Plane,
+    tile_size: f32,
+}
+
+impl TexturedPlane {
+    pub fn new(
+        point: Vec3,
+        normal: Vec3,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> TexturedPlane {
+        TexturedPlane {
+            plane: Plane::new(point, normal, color, reflection_factor, transparency_factor),
+            tile_size: 1.0,
+        }
+    }
+
+    pub fn with_extents(self, width: f32, height: f32) -> TexturedPlane {
+        TexturedPlane {
+            plane: self.plane.with_extents(width, height),
+            ..self
+        }
+    }
+
+    pub fn with_tile_size(self, tile_size: f32) -> TexturedPlane {
+        TexturedPlane { tile_size, ..self }
+    }
+}
+
+impl Geometry for TexturedPlane {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        self.plane.compute_hit(ray, hitinfo, exit_dist)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        let black = Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        };
+
+        CheckerTexture::new(
+            self.plane.get_color(),
+            black,
+            self.tile_size,
+            TextureMapping::Uv,
+        )
+        .with_mirrored_rows()
+        .evaluate(hit_info)
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.plane.get_reflection_factor(hit_info)
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.plane.get_transparency_factor(hit_info)
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.plane.get_bounds()
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        self.plane.get_intervals(ray)
+    }
+}
@@ -1,11 +1,13 @@
+use super::bounds::BoundingBox;
 use super::color::Color;
-use super::geometry::Geometry;
-use super::geometry::HitInfo;
+use super::geometry::{Geometry, HitInfo, Interval};
+use super::motion::Motion;
 use super::ray::Ray;
 use super::sphere::Sphere;
+use super::texture::{CheckerTexture, Texture, TextureMapping};
 use super::vec3::Vec3;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TexturedSphere {
     sphere: Sphere,
 }
@@ -28,6 +30,12 @@ impl TexturedSphere {
             ),
         }
     }
+
+    pub fn with_motion(self, motion: Motion) -> TexturedSphere {
+        TexturedSphere {
+            sphere: self.sphere.with_motion(motion),
+        }
+    }
 }
 
 impl Geometry for TexturedSphere {
@@ -40,29 +48,31 @@ impl Geometry for TexturedSphere {
         self.sphere.compute_hit(ray, hitinfo, exit_dist)
     }
 
-    fn get_color(&self, position: &Vec3) -> Color {
-        let up = Vec3::new(0.0, 1.0, 0.0);
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        let black = Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        };
 
-        let size = 1.0;
+        CheckerTexture::new(self.sphere.get_color(), black, 1.0, TextureMapping::Planar)
+            .with_mirrored_rows()
+            .evaluate(hit_info)
+    }
 
-        let is_even = (position.z % size).abs() > size / 2.0;
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.sphere.get_reflection_factor(hit_info)
+    }
 
-        if (position.x.rem_euclid(size) > size / 2.0) ^ is_even {
-            self.sphere.get_color()
-        } else {
-            Color {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-            }
-        }
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> std::option::Option<f32> {
+        self.sphere.get_transparency_factor(hit_info)
     }
 
-    fn get_reflection_factor(&self) -> Option<f32> {
-        self.sphere.get_reflection_factor()
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        self.sphere.get_bounds()
     }
 
-    fn get_transparency_factor(&self) -> std::option::Option<f32> {
-        self.sphere.get_transparency_factor()
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        self.sphere.get_intervals(ray)
     }
 }
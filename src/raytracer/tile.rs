@@ -0,0 +1,35 @@
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub index: usize,
+    pub min_x: usize,
+    pub min_y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    // Splits an image into square tiles, the last row and column being cropped to the image size
+    pub fn split(width: usize, height: usize, side: usize) -> Vec<Tile> {
+        let tile_count_x = width.div_ceil(side);
+        let tile_count_y = height.div_ceil(side);
+
+        (0..tile_count_x * tile_count_y)
+            .map(|index| {
+                let min_x = (index % tile_count_x) * side;
+                let min_y = (index / tile_count_x) * side;
+
+                Tile {
+                    index,
+                    min_x,
+                    min_y,
+                    width: (min_x + side).min(width) - min_x,
+                    height: (min_y + side).min(height) - min_y,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+}
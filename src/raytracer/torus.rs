@@ -0,0 +1,191 @@
+use super::bounds::BoundingBox;
+use super::color::Color;
+use super::geometry::{first_hit, pair_intervals, Geometry, HitInfo, Interval};
+use super::ray::Ray;
+use super::solver::solve_quartic;
+use super::transform::Frame;
+use super::vec3::Vec3;
+use std::f32::consts::PI;
+
+// Torus lying in the plane perpendicular to its axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Torus {
+    color: Color,
+    frame: Frame,
+    major_radius: f32,
+    minor_radius: f32,
+    reflection_factor: Option<f32>,
+    transparency_factor: Option<f32>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        color: Color,
+        reflection_factor: f32,
+        transparency_factor: f32,
+    ) -> Torus {
+        Torus {
+            color,
+            frame: Frame::new(center, axis),
+            major_radius,
+            minor_radius,
+            reflection_factor: if reflection_factor > 0.001 {
+                Some(reflection_factor)
+            } else {
+                None
+            },
+            transparency_factor: if transparency_factor > 0.001 {
+                Some(transparency_factor)
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    pub fn get_major_radius(&self) -> f32 {
+        self.major_radius
+    }
+
+    pub fn get_minor_radius(&self) -> f32 {
+        self.minor_radius
+    }
+
+    // u goes around the axis and v around the tube, both from 0 to 1
+    pub fn get_uv(&self, position: &Vec3) -> (f32, f32) {
+        let local = self.frame.local_point(position);
+        let distance_to_axis = (local.x * local.x + local.z * local.z).sqrt();
+        (
+            local.z.atan2(local.x) / (2.0 * PI) + 0.5,
+            local.y.atan2(distance_to_axis - self.major_radius) / (2.0 * PI) + 0.5,
+        )
+    }
+
+    // Distances where the ray crosses the surface, with the normals there
+    fn get_hits(&self, ray: &Ray) -> Vec<(f32, Vec3)> {
+        let mut origin = self.frame.local_point(ray.get_origin());
+        let direction = self.frame.local_vector(ray.get_direction());
+
+        // Starting the ray close to the torus keeps the quartic well conditioned
+        let bounding_radius = self.major_radius + self.minor_radius;
+        let start = (-Vec3::dot_product(&origin, &direction) - bounding_radius).max(0.0);
+        origin = origin + direction * start;
+
+        let major2 = (self.major_radius * self.major_radius) as f64;
+        let minor2 = (self.minor_radius * self.minor_radius) as f64;
+        let (ox, oy, oz) = (origin.x as f64, origin.y as f64, origin.z as f64);
+        let (dx, dy, dz) = (direction.x as f64, direction.y as f64, direction.z as f64);
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2), with p = o + t d and |d| = 1
+        let dot_od = ox * dx + oy * dy + oz * dz;
+        let dot_oo = ox * ox + oy * oy + oz * oz;
+        let h = dot_oo + major2 - minor2;
+
+        let roots = solve_quartic(
+            1.0,
+            4.0 * dot_od,
+            4.0 * dot_od * dot_od + 2.0 * h - 4.0 * major2 * (1.0 - dy * dy),
+            4.0 * dot_od * h - 8.0 * major2 * (dot_od - oy * dy),
+            h * h - 4.0 * major2 * (dot_oo - oy * oy),
+        );
+
+        roots
+            .into_iter()
+            .map(|root| {
+                let position = origin + direction * root as f32;
+                let s = position.squared_length() + self.major_radius * self.major_radius
+                    - self.minor_radius * self.minor_radius;
+                let radial = s - 2.0 * self.major_radius * self.major_radius;
+                let normal = Vec3::new(position.x * radial, position.y * s, position.z * radial);
+
+                (root as f32 + start, self.frame.world_vector(&normal))
+            })
+            .collect()
+    }
+
+    fn get_hit_info(&self, position: Vec3, normal: Vec3) -> HitInfo {
+        let mut hit_info = HitInfo::new();
+        hit_info.position = position;
+        hit_info.normal = normal;
+        hit_info.geometric_normal = normal;
+        hit_info.uv = self.get_uv(&position);
+
+        let local = self.frame.local_point(&position);
+        let radial = (local.x * local.x + local.z * local.z).sqrt().max(1e-6);
+        hit_info.dpdu = self
+            .frame
+            .world_vector(&(2.0 * PI * Vec3::new(-local.z, 0.0, local.x)));
+        hit_info.dpdv = self.frame.world_vector(
+            &(2.0
+                * PI
+                * Vec3::new(
+                    -local.y * local.x / radial,
+                    radial - self.major_radius,
+                    -local.y * local.z / radial,
+                )),
+        );
+
+        hit_info
+    }
+}
+
+impl Geometry for Torus {
+    fn compute_hit(
+        &self,
+        ray: &Ray,
+        hitinfo: Option<&mut HitInfo>,
+        exit_dist: Option<&mut f32>,
+    ) -> Option<f32> {
+        let mut hits = self.get_hits(ray);
+
+        // A tangent ray may only give one root of a pair
+        let closed = hits.len() % 2 == 0;
+        let (distance, normal, exit_distance) = first_hit(&mut hits, closed)?;
+
+        if let Some(exit_dist) = exit_dist {
+            *exit_dist = exit_distance
+        }
+
+        if let Some(hit_info) = hitinfo {
+            *hit_info = self.get_hit_info(ray.point_at(distance), normal);
+        }
+
+        Some(distance)
+    }
+
+    fn get_color(&self, hit_info: &HitInfo) -> Color {
+        self.get_color()
+    }
+
+    fn get_reflection_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.reflection_factor
+    }
+
+    fn get_transparency_factor(&self, hit_info: &HitInfo) -> Option<f32> {
+        self.transparency_factor
+    }
+
+    fn get_bounds(&self) -> Option<BoundingBox> {
+        let bounds = BoundingBox::from_disk(
+            &self.frame.get_origin(),
+            &self.frame.get_axis(),
+            self.major_radius,
+        );
+        let tube = Vec3::new(self.minor_radius, self.minor_radius, self.minor_radius);
+
+        Some(BoundingBox::new(bounds.min - tube, bounds.max + tube))
+    }
+
+    fn get_intervals(&self, ray: &Ray) -> Vec<Interval> {
+        pair_intervals(&mut self.get_hits(ray), |distance, normal| {
+            self.get_hit_info(ray.point_at(distance), normal)
+        })
+    }
+}
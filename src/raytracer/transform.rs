@@ -0,0 +1,406 @@
+use super::vec3::Vec3;
+use std::ops::Mul;
+
+// Row-major 4x4 matrix, vectors being columns
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f32; 4]; 4]) -> Matrix4 {
+        Matrix4 { m }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut result = Matrix4::identity();
+        for (i, row) in result.m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+
+        result
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None if the matrix is singular
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.m;
+        let mut inverse = Matrix4::identity().m;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let inv_pivot = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= inv_pivot;
+                inverse[column][j] *= inv_pivot;
+            }
+
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+
+                let factor = a[row][column];
+                if factor != 0.0 {
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[column][j];
+                        inverse[row][j] -= factor * inverse[column][j];
+                    }
+                }
+            }
+        }
+
+        Some(Matrix4 { m: inverse })
+    }
+
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        let m = &self.m;
+        let x = m[0][0] * point.x + m[0][1] * point.y + m[0][2] * point.z + m[0][3];
+        let y = m[1][0] * point.x + m[1][1] * point.y + m[1][2] * point.z + m[1][3];
+        let z = m[2][0] * point.x + m[2][1] * point.y + m[2][2] * point.z + m[2][3];
+        let w = m[3][0] * point.x + m[3][1] * point.y + m[3][2] * point.z + m[3][3];
+
+        if w == 1.0 || w == 0.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x / w, y / w, z / w)
+        }
+    }
+
+    pub fn transform_vector(&self, vector: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * vector.x + m[0][1] * vector.y + m[0][2] * vector.z,
+            m[1][0] * vector.x + m[1][1] * vector.y + m[1][2] * vector.z,
+            m[2][0] * vector.x + m[2][1] * vector.y + m[2][2] * vector.z,
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut result = Matrix4 { m: [[0.0; 4]; 4] };
+        for (i, row) in result.m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+
+        result
+    }
+}
+
+// Affine transformation keeping its inverse around, to go back and forth between object and world space
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Option<Transform> {
+        matrix
+            .inverse()
+            .map(|inverse| Transform { matrix, inverse })
+    }
+
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    pub fn translation(offset: Vec3) -> Transform {
+        let matrix = Matrix4::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        let inverse = Matrix4::new([
+            [1.0, 0.0, 0.0, -offset.x],
+            [0.0, 1.0, 0.0, -offset.y],
+            [0.0, 0.0, 1.0, -offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Transform { matrix, inverse }
+    }
+
+    pub fn scale(factors: Vec3) -> Transform {
+        let matrix = Matrix4::new([
+            [factors.x, 0.0, 0.0, 0.0],
+            [0.0, factors.y, 0.0, 0.0],
+            [0.0, 0.0, factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        let inverse = Matrix4::new([
+            [1.0 / factors.x, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / factors.y, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Transform { matrix, inverse }
+    }
+
+    pub fn uniform_scale(factor: f32) -> Transform {
+        Transform::scale(Vec3::new(factor, factor, factor))
+    }
+
+    // Rotation around an axis, angle in degrees
+    pub fn rotation(axis: Vec3, angle: f32) -> Transform {
+        let mut axis = axis;
+        axis.normalize();
+
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+
+        let matrix = Matrix4::new([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        // Rotations are orthogonal
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    pub fn rotation_x(angle: f32) -> Transform {
+        Transform::rotation(Vec3::new(1.0, 0.0, 0.0), angle)
+    }
+
+    pub fn rotation_y(angle: f32) -> Transform {
+        Transform::rotation(Vec3::new(0.0, 1.0, 0.0), angle)
+    }
+
+    pub fn rotation_z(angle: f32) -> Transform {
+        Transform::rotation(Vec3::new(0.0, 0.0, 1.0), angle)
+    }
+
+    pub fn get_matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn get_inverse_matrix(&self) -> &Matrix4 {
+        &self.inverse
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        self.matrix.transform_point(point)
+    }
+
+    pub fn transform_vector(&self, vector: &Vec3) -> Vec3 {
+        self.matrix.transform_vector(vector)
+    }
+
+    // Normals are transformed by the inverse transpose to stay perpendicular to surfaces
+    pub fn transform_normal(&self, normal: &Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(normal)
+    }
+
+    pub fn inverse_transform_point(&self, point: &Vec3) -> Vec3 {
+        self.inverse.transform_point(point)
+    }
+
+    pub fn inverse_transform_vector(&self, vector: &Vec3) -> Vec3 {
+        self.inverse.transform_vector(vector)
+    }
+
+    pub fn inverse_transform_normal(&self, normal: &Vec3) -> Vec3 {
+        self.matrix.transpose().transform_vector(normal)
+    }
+}
+
+// `a * b` applies b first, then a
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, other: Transform) -> Transform {
+        Transform {
+            matrix: self.matrix * other.matrix,
+            inverse: other.inverse * self.inverse,
+        }
+    }
+}
+
+// Orthonormal frame around an axis, local coordinates having y along the axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame {
+    origin: Vec3,
+    u_axis: Vec3,
+    axis: Vec3,
+    v_axis: Vec3,
+}
+
+impl Frame {
+    pub fn new(origin: Vec3, axis: Vec3) -> Frame {
+        let mut axis = axis;
+        axis.normalize();
+
+        let (u_axis, v_axis) = Vec3::orthonormal_basis(&axis);
+
+        Frame {
+            origin,
+            u_axis,
+            axis,
+            v_axis,
+        }
+    }
+
+    pub fn get_origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    pub fn get_axis(&self) -> Vec3 {
+        self.axis
+    }
+
+    pub fn local_point(&self, point: &Vec3) -> Vec3 {
+        self.local_vector(&(point - self.origin))
+    }
+
+    pub fn local_vector(&self, vector: &Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot_product(vector, &self.u_axis),
+            Vec3::dot_product(vector, &self.axis),
+            Vec3::dot_product(vector, &self.v_axis),
+        )
+    }
+
+    pub fn world_vector(&self, vector: &Vec3) -> Vec3 {
+        self.u_axis * vector.x + self.axis * vector.y + self.v_axis * vector.z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_identity(matrix: &Matrix4) {
+        for (i, row) in matrix.m.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((value - expected).abs() < 1e-5, "{:?}", matrix);
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_times_its_inverse_is_identity() {
+        let matrix = Matrix4::new([
+            [0.0, 2.0, -1.0, 3.0],
+            [1.5, 0.5, 0.0, -2.0],
+            [-1.0, 0.0, 4.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inverse = matrix.inverse().unwrap();
+
+        assert_identity(&(matrix * inverse));
+        assert_identity(&(inverse * matrix));
+
+        let transform = Transform::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Transform::rotation(Vec3::new(1.0, 1.0, 0.0), 30.0)
+            * Transform::scale(Vec3::new(2.0, 1.0, 0.5));
+        assert_identity(&(*transform.get_matrix() * transform.get_matrix().inverse().unwrap()));
+        assert_identity(&(*transform.get_matrix() * *transform.get_inverse_matrix()));
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        // The third row is the sum of the first two
+        let matrix = Matrix4::new([
+            [1.0, 2.0, 3.0, 0.0],
+            [0.0, 1.0, 4.0, 0.0],
+            [1.0, 3.0, 7.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        assert!(matrix.inverse().is_none());
+        assert!(Transform::new(matrix).is_none());
+        assert!(Transform::scale(Vec3::new(1.0, 0.0, 1.0))
+            .get_matrix()
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let transform = Transform::rotation_z(40.0) * Transform::scale(Vec3::new(3.0, 1.0, 0.25));
+
+        // Tangents of a slanted plane, and its normal
+        let tangents = [Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, -2.0)];
+        let normal = Vec3::cross_product(&tangents[0], &tangents[1]);
+
+        let world_normal = transform.transform_normal(&normal);
+        for tangent in tangents.iter() {
+            let world_tangent = transform.transform_vector(tangent);
+            assert!(Vec3::dot_product(&world_normal, &world_tangent).abs() < 1e-5);
+        }
+
+        // Transforming the normal as a vector would tilt it
+        let tilted = transform.transform_vector(&normal);
+        assert!(Vec3::dot_product(&tilted, &transform.transform_vector(&tangents[0])).abs() > 0.1);
+
+        // And the inverse brings it back
+        let mut back = transform.inverse_transform_normal(&world_normal);
+        back.normalize();
+        let mut expected = normal;
+        expected.normalize();
+        assert!((back - expected).length() < 1e-5);
+    }
+}
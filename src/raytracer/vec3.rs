@@ -45,7 +45,7 @@ impl Vec3 {
     pub fn cross_product(left: &Vec3, right: &Vec3) -> Vec3 {
         Vec3 {
             x: left.y * right.z - left.z * right.y,
-            y: left.x * right.z - left.z * right.x,
+            y: left.z * right.x - left.x * right.z,
             z: left.x * right.y - left.y * right.x,
         }
     }
@@ -53,6 +53,22 @@ impl Vec3 {
     pub fn dot_product(left: &Vec3, right: &Vec3) -> f32 {
         left.x * right.x + left.y * right.y + left.z * right.z
     }
+
+    // Two unit vectors (u, v) such as (u, normal, v) is a right-handed basis,
+    // u following x and v following z for an upward normal
+    pub fn orthonormal_basis(normal: &Vec3) -> (Vec3, Vec3) {
+        let reference = if normal.x.abs() < 0.9 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+
+        let mut v = Vec3::cross_product(&reference, normal);
+        v.normalize();
+        let u = Vec3::cross_product(normal, &v);
+
+        (u, v)
+    }
 }
 
 impl Add for Vec3 {